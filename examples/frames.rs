@@ -1,4 +1,4 @@
-use helios_dac::{Frame, Point, Coordinate, Color};
+use helios_dac::{Frame, Point, Color};
 
 #[cfg(all(feature = "sdk", not(feature = "native")))]
 pub fn main() {
@@ -46,7 +46,7 @@ fn get_frames() -> Vec<Frame> {
 
     for i in 0..30 {
         let mut points = vec![];
-        let mut y = (i * 0xFFF / 30) as u16;
+        let y = (i * 0xFFF / 30) as u16;
         for j in 0..1000 {
             let x = if j < 500 {
                 j * 0xFFF / 500
@@ -26,6 +26,6 @@ pub fn main() {
         let status = device.status().unwrap();
         let version = device.firmware_version().unwrap();
 
-        println!("{:?} FW v{} - Status: {:?}", name, version, status);
+        println!("{:?} FW {} - Status: {:?}", name, version, status);
     }
 }
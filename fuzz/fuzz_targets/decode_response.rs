@@ -0,0 +1,9 @@
+#![no_main]
+
+use helios_dac::protocol::{self, Command, ReadFromBytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = protocol::decode_response(data);
+    let _ = Command::read_from_bytes(data);
+});
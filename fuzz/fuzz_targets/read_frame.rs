@@ -0,0 +1,9 @@
+#![no_main]
+
+use helios_dac::protocol::ReadFromBytes;
+use helios_dac::Frame;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Frame::read_from_bytes(data);
+});
@@ -0,0 +1,127 @@
+use std::future;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_core::Stream;
+
+use crate::{DeviceStatus, FirmwareVersion, Frame, NativeHeliosDac, NativeHeliosError};
+
+type Result<T> = std::result::Result<T, NativeHeliosError>;
+
+/// Async version of [NativeHeliosDac]
+///
+/// libusb transfers are blocking so every call is run on tokio's blocking thread pool.
+#[derive(Clone)]
+pub struct AsyncNativeHeliosDac {
+    dac: Arc<Mutex<NativeHeliosDac>>,
+}
+
+impl AsyncNativeHeliosDac {
+    /// Opens the given dac
+    pub async fn open(dac: NativeHeliosDac) -> Result<Self> {
+        let dac = tokio::task::spawn_blocking(move || dac.open())
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))?;
+
+        Ok(dac.into())
+    }
+
+    /// writes and outputs a frame to the dac
+    pub async fn write_frame(&self, frame: Frame) -> Result<()> {
+        self.run(move |dac| dac.write_frame(frame)).await
+    }
+
+    /// Gets name of dac
+    pub async fn name(&self) -> Result<String> {
+        self.run(|dac| dac.name()).await
+    }
+
+    /// Sets name of dac
+    pub async fn set_name(&self, name: String) -> Result<()> {
+        self.run(move |dac| dac.set_name(&name)).await
+    }
+
+    /// Get firmware version
+    pub async fn firmware_version(&self) -> Result<FirmwareVersion> {
+        self.run(|dac| dac.firmware_version()).await
+    }
+
+    pub async fn status(&self) -> Result<DeviceStatus> {
+        self.run(|dac| dac.status()).await
+    }
+
+    /// Opens or closes the shutter of the dac
+    pub async fn set_shutter(&self, open: bool) -> Result<()> {
+        self.run(move |dac| dac.set_shutter(open)).await
+    }
+
+    /// Stops output of DAC
+    pub async fn stop(&self) -> Result<()> {
+        self.run(|dac| dac.stop()).await
+    }
+
+    /// Writes each frame of `frames` as soon as the dac is ready for it
+    ///
+    /// The next frame is only taken from the stream once the previous one was written,
+    /// so a slow dac slows down the stream instead of frames being dropped.
+    /// The last frame keeps being drawn when the stream ends. Dropping the returned future or a failed write
+    /// stops the output.
+    pub async fn play_stream(&self, frames: impl Stream<Item = Frame>) -> Result<()> {
+        let mut frames = pin!(frames);
+        let mut guard = StopGuard {
+            dac: Some(Arc::clone(&self.dac)),
+        };
+        while let Some(frame) = future::poll_fn(|cx| frames.as_mut().poll_next(cx)).await {
+            while !self.status().await?.ready() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            self.write_frame(frame).await?;
+        }
+        guard.dac = None;
+
+        Ok(())
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T>
+        where T: Send + 'static,
+              F: FnOnce(&mut NativeHeliosDac) -> Result<T> + Send + 'static {
+        let dac = Arc::clone(&self.dac);
+        tokio::task::spawn_blocking(move || {
+            let mut dac = dac.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut dac)
+        })
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+    }
+}
+
+/// Stops the output of the dac unless it was disarmed by taking the dac
+struct StopGuard {
+    dac: Option<Arc<Mutex<NativeHeliosDac>>>,
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        if let Some(dac) = self.dac.take() {
+            let stop = move || {
+                let dac = dac.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                // errors can't be reported here, the dac might already be unplugged
+                let _ = dac.stop();
+            };
+            // a write still running on the blocking pool holds the lock, so the stop is sent after it
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => drop(handle.spawn_blocking(stop)),
+                Err(_) => stop(),
+            }
+        }
+    }
+}
+
+impl From<NativeHeliosDac> for AsyncNativeHeliosDac {
+    fn from(dac: NativeHeliosDac) -> Self {
+        AsyncNativeHeliosDac {
+            dac: Arc::new(Mutex::new(dac)),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Correction of projector distortion
+
+use crate::frame::{Coordinate, Point};
+
+/// Perspective warp remapping coordinates to correct keystone distortion of a projector
+///
+/// The warp is a homography, which maps straight lines onto straight lines.
+/// Results are clamped to the coordinate space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    matrix: [[f64; 3]; 3],
+}
+
+impl Calibration {
+    /// Calibration which leaves all coordinates as they are
+    pub fn identity() -> Self {
+        Calibration {
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    /// Calibration mapping each of the four coordinates in `from` onto the coordinate at the same index in `to`
+    ///
+    /// Usually `from` are the corners of the coordinate space and `to` the coordinates which have to be sent
+    /// for the projection to hit the corners of the target area.
+    /// Fails when three of the coordinates in `from` or `to` are on a line, as no warp maps them.
+    pub fn from_corners(from: [Coordinate; 4], to: [Coordinate; 4]) -> Result<Self, DegenerateCorners> {
+        if has_collinear_corners(&from) || has_collinear_corners(&to) {
+            return Err(DegenerateCorners);
+        }
+        let mut system = [[0.0; 9]; 8];
+        for (index, (from, to)) in from.iter().zip(to.iter()).enumerate() {
+            let (x, y) = (f64::from(from.x), f64::from(from.y));
+            let (u, v) = (f64::from(to.x), f64::from(to.y));
+            system[index * 2] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u, u];
+            system[index * 2 + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v, v];
+        }
+        let h = solve(system).ok_or(DegenerateCorners)?;
+        if h.iter().any(|value| !value.is_finite()) {
+            return Err(DegenerateCorners);
+        }
+
+        Ok(Calibration {
+            matrix: [[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]],
+        })
+    }
+
+    /// Remaps a single coordinate
+    pub fn apply(&self, coordinate: Coordinate) -> Coordinate {
+        let x = f64::from(coordinate.x.min(Coordinate::MAX));
+        let y = f64::from(coordinate.y.min(Coordinate::MAX));
+        let [a, b, c] = self.matrix;
+        let w = c[0] * x + c[1] * y + c[2];
+        if w.abs() < f64::EPSILON {
+            return Coordinate {
+                x: clamp(x),
+                y: clamp(y),
+            };
+        }
+
+        Coordinate {
+            x: clamp((a[0] * x + a[1] * y + a[2]) / w),
+            y: clamp((b[0] * x + b[1] * y + b[2]) / w),
+        }
+    }
+
+    /// Remaps the coordinates of all points
+    pub fn apply_to(&self, points: &mut [Point]) {
+        for point in points {
+            point.coordinate = self.apply(point.coordinate);
+        }
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration::identity()
+    }
+}
+
+/// Three of the corners passed to [Calibration::from_corners] are on a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DegenerateCorners;
+
+impl std::fmt::Display for DegenerateCorners {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "three of the calibration corners are on a line")
+    }
+}
+
+impl std::error::Error for DegenerateCorners {}
+
+/// Whether any three of the corners are on a line, which includes corners at the same position
+fn has_collinear_corners(corners: &[Coordinate; 4]) -> bool {
+    let cross = |a: Coordinate, b: Coordinate, c: Coordinate| {
+        let (ax, ay) = (i64::from(a.x), i64::from(a.y));
+        (i64::from(b.x) - ax) * (i64::from(c.y) - ay) - (i64::from(b.y) - ay) * (i64::from(c.x) - ax)
+    };
+
+    (0..4).any(|skipped| {
+        let mut others = (0..4).filter(|&index| index != skipped).map(|index| corners[index]);
+        let (a, b, c) = (others.next().unwrap(), others.next().unwrap(), others.next().unwrap());
+        cross(a, b, c) == 0
+    })
+}
+
+/// Solves an augmented system of 8 linear equations with gaussian elimination
+fn solve(mut system: [[f64; 9]; 8]) -> Option<[f64; 8]> {
+    for column in 0..8 {
+        let pivot = (column..8).max_by(|a, b| system[*a][column].abs().total_cmp(&system[*b][column].abs()))?;
+        if system[pivot][column].abs() < 1e-9 {
+            return None;
+        }
+        system.swap(column, pivot);
+        let pivot = system[column];
+        for (index, row) in system.iter_mut().enumerate() {
+            if index != column {
+                let factor = row[column] / pivot[column];
+                for (value, pivot) in row[column..].iter_mut().zip(&pivot[column..]) {
+                    *value -= factor * pivot;
+                }
+            }
+        }
+    }
+    let mut solution = [0.0; 8];
+    for (index, value) in solution.iter_mut().enumerate() {
+        *value = system[index][8] / system[index][index];
+    }
+
+    Some(solution)
+}
+
+fn clamp(value: f64) -> u16 {
+    value.round().clamp(0.0, f64::from(Coordinate::MAX)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORNERS: [Coordinate; 4] = [
+        Coordinate { x: 0, y: 0 },
+        Coordinate { x: Coordinate::MAX, y: 0 },
+        Coordinate { x: Coordinate::MAX, y: Coordinate::MAX },
+        Coordinate { x: 0, y: Coordinate::MAX },
+    ];
+
+    fn grid() -> impl Iterator<Item = Coordinate> {
+        (0..=Coordinate::MAX)
+            .step_by(273)
+            .flat_map(|x| (0..=Coordinate::MAX).step_by(195).map(move |y| Coordinate { x, y }))
+    }
+
+    #[test]
+    fn identity_corners_give_the_identity() {
+        let calibration = Calibration::from_corners(CORNERS, CORNERS).unwrap();
+
+        for coordinate in grid().chain(CORNERS.iter().copied()) {
+            assert_eq!(calibration.apply(coordinate), coordinate);
+            assert_eq!(Calibration::identity().apply(coordinate), coordinate);
+        }
+    }
+
+    #[test]
+    fn corners_map_exactly_onto_their_targets() {
+        let to = [
+            Coordinate { x: 300, y: 120 },
+            Coordinate { x: 3900, y: 0 },
+            Coordinate { x: 3500, y: 4000 },
+            Coordinate { x: 100, y: 3700 },
+        ];
+        let calibration = Calibration::from_corners(CORNERS, to).unwrap();
+
+        for (from, to) in CORNERS.iter().zip(&to) {
+            assert_eq!(calibration.apply(*from), *to);
+        }
+
+        let inverse = Calibration::from_corners(to, CORNERS).unwrap();
+        for (from, to) in to.iter().zip(&CORNERS) {
+            assert_eq!(inverse.apply(*from), *to);
+        }
+    }
+
+    #[test]
+    fn results_are_clamped_to_the_coordinate_space() {
+        let to = [
+            Coordinate { x: 1000, y: 1000 },
+            Coordinate { x: 3000, y: 1000 },
+            Coordinate { x: 3000, y: 3000 },
+            Coordinate { x: 1000, y: 3000 },
+        ];
+        let calibration = Calibration::from_corners(to, CORNERS).unwrap();
+
+        assert_eq!(calibration.apply(Coordinate { x: 0, y: 0 }), Coordinate { x: 0, y: 0 });
+        assert_eq!(calibration.apply(Coordinate { x: 4000, y: 4000 }), Coordinate { x: Coordinate::MAX, y: Coordinate::MAX });
+    }
+
+    #[test]
+    fn collinear_corners_are_rejected() {
+        let mut collinear = CORNERS;
+        collinear[1] = Coordinate { x: 2000, y: 2000 };
+        let mut duplicate = CORNERS;
+        duplicate[3] = CORNERS[2];
+        let line = [
+            Coordinate { x: 0, y: 100 },
+            Coordinate { x: 1000, y: 100 },
+            Coordinate { x: 2000, y: 100 },
+            Coordinate { x: 3000, y: 100 },
+        ];
+
+        for degenerate in [collinear, duplicate, line] {
+            assert_eq!(Calibration::from_corners(degenerate, CORNERS), Err(DegenerateCorners));
+            assert_eq!(Calibration::from_corners(CORNERS, degenerate), Err(DegenerateCorners));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Color correction passes over the points of a frame
+
+use crate::frame::{Color, Point};
+
+/// Applies the gamma curve `value ^ gamma` to all color channels
+pub fn apply_gamma(points: &mut [Point], gamma: f32) {
+    let table = gamma_table(gamma);
+    apply_tables(points, &table, &table, &table);
+}
+
+/// Applies a separate gamma curve to each color channel
+pub fn apply_gamma_rgb(points: &mut [Point], red: f32, green: f32, blue: f32) {
+    apply_tables(points, &gamma_table(red), &gamma_table(green), &gamma_table(blue));
+}
+
+/// Multiplies each color channel by its scale, saturating at 255
+///
+/// Scales above 1.0 boost a channel. The `intensity` of the points is left untouched.
+pub fn scale_channels(points: &mut [Point], red: f32, green: f32, blue: f32) {
+    for point in points {
+        point.color.r = scale(point.color.r, red);
+        point.color.g = scale(point.color.g, green);
+        point.color.b = scale(point.color.b, blue);
+    }
+}
+
+/// Shifts the hue of all points by `degrees`, saturation and value are kept
+pub fn hue_rotate(points: &mut [Point], degrees: f32) {
+    for point in points {
+        let (h, s, v) = rgb_to_hsv([point.color.r, point.color.g, point.color.b]);
+        let [r, g, b] = hsv_to_rgb(h + degrees, s, v);
+        point.color = Color::new(r, g, b);
+    }
+}
+
+/// Ramps the color and intensity up over the first `ramp_points` points after each blanked point
+/// and down over the last `ramp_points` points before each blanked point
+///
+/// This softens the edges of lines like the analog modulation of a laser would.
+/// As frames repeat, the last point counts as coming right before the first one.
+/// Points are scaled by the smaller factor when both ramps overlap on short lines.
+pub fn soft_start(points: &mut [Point], ramp_points: u8) {
+    let length = points.len();
+    if ramp_points == 0 || length == 0 {
+        return;
+    }
+    let steps = f32::from(ramp_points) + 1.0;
+    let lit = points.iter().map(|point| !point.is_blanked()).collect::<Vec<_>>();
+    let mut start = 0;
+    while start < length {
+        if !lit[start] {
+            start += 1;
+            continue;
+        }
+        let end = (start..length).find(|index| !lit[*index]).unwrap_or(length);
+        let ramp_up = start > 0 || !lit[length - 1];
+        let ramp_down = end < length || !lit[0];
+        for (offset, point) in points[start..end].iter_mut().enumerate() {
+            let mut factor = 1.0f32;
+            if ramp_up {
+                factor = factor.min((offset as f32 + 1.0) / steps);
+            }
+            if ramp_down {
+                factor = factor.min((end - start - offset) as f32 / steps);
+            }
+            if factor < 1.0 {
+                point.color.r = scale(point.color.r, factor);
+                point.color.g = scale(point.color.g, factor);
+                point.color.b = scale(point.color.b, factor);
+                point.intensity = scale(point.intensity, factor);
+            }
+        }
+        start = end;
+    }
+}
+
+/// Converts a color from hsv to rgb
+///
+/// The hue is given in degrees and wraps around, saturation and value range from 0.0 to 1.0.
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    let chroma = v * s;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = v - chroma;
+
+    [to_channel(r + m), to_channel(g + m), to_channel(b + m)]
+}
+
+/// Converts a color from rgb to hsv, inverse of [hsv_to_rgb]
+///
+/// The hue of grays is 0.
+pub fn rgb_to_hsv([r, g, b]: [u8; 3]) -> (f32, f32, f32) {
+    let (r, g, b) = (f32::from(r) / 255.0, f32::from(g) / 255.0, f32::from(b) / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let h = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { chroma / max };
+
+    (h, s, max)
+}
+
+fn to_channel(value: f32) -> u8 {
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+fn scale(value: u8, factor: f32) -> u8 {
+    (f32::from(value) * factor).round().clamp(0.0, 255.0) as u8
+}
+
+fn apply_tables(points: &mut [Point], red: &[u8; 256], green: &[u8; 256], blue: &[u8; 256]) {
+    for point in points {
+        point.color.r = red[usize::from(point.color.r)];
+        point.color.g = green[usize::from(point.color.g)];
+        point.color.b = blue[usize::from(point.color.b)];
+    }
+}
+
+fn gamma_table(gamma: f32) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        *entry = ((value as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Colors on a grid including 0 and 255 for each channel
+    fn colors() -> impl Iterator<Item = [u8; 3]> {
+        let channel = || (0..=255).step_by(5);
+        channel().flat_map(move |r| channel().flat_map(move |g| channel().map(move |b| [r, g, b])))
+    }
+
+    fn point([r, g, b]: [u8; 3]) -> Point {
+        Point::builder().rgb(r, g, b).build()
+    }
+
+    fn assert_within_one_lsb(expected: [u8; 3], actual: [u8; 3]) {
+        for (a, b) in expected.iter().zip(actual.iter()) {
+            assert!((i16::from(*a) - i16::from(*b)).abs() <= 1, "{:?} became {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn hsv_round_trips_within_one_lsb() {
+        for rgb in colors() {
+            let (h, s, v) = rgb_to_hsv(rgb);
+            assert_within_one_lsb(rgb, hsv_to_rgb(h, s, v));
+        }
+    }
+
+    #[test]
+    fn hsv_of_primaries() {
+        assert_eq!(rgb_to_hsv([255, 0, 0]), (0.0, 1.0, 1.0));
+        assert_eq!(rgb_to_hsv([0, 255, 0]), (120.0, 1.0, 1.0));
+        assert_eq!(rgb_to_hsv([0, 0, 255]), (240.0, 1.0, 1.0));
+        assert_eq!(rgb_to_hsv([0, 0, 0]), (0.0, 0.0, 0.0));
+        assert_eq!(hsv_to_rgb(-120.0, 1.0, 1.0), [0, 0, 255]);
+        assert_eq!(hsv_to_rgb(480.0, 1.0, 1.0), [0, 255, 0]);
+    }
+
+    #[test]
+    fn hue_rotate_keeps_saturation_and_value() {
+        for rgb in colors() {
+            let mut points = [point(rgb)];
+            hue_rotate(&mut points, 100.0);
+            let color = points[0].color;
+            let (_, s, v) = rgb_to_hsv(rgb);
+            let (_, rotated_s, rotated_v) = rgb_to_hsv([color.r, color.g, color.b]);
+
+            assert!((v - rotated_v).abs() <= 1.0 / 255.0 + f32::EPSILON, "{:?} became {:?}", rgb, color);
+            if v > 0.0 {
+                // one lsb of rounding in chroma and value
+                assert!((s - rotated_s).abs() <= 2.0 / 255.0 / v + f32::EPSILON, "{:?} became {:?}", rgb, color);
+            }
+        }
+    }
+
+    #[test]
+    fn hue_rotate_by_full_turn_keeps_colors() {
+        for rgb in colors() {
+            let mut points = [point(rgb)];
+            hue_rotate(&mut points, 360.0);
+            let color = points[0].color;
+            assert_within_one_lsb(rgb, [color.r, color.g, color.b]);
+        }
+    }
+}
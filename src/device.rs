@@ -1,6 +1,6 @@
 use crate::wrapper::{HeliosDacWrapper, HeliosError};
 use crate::frame::Frame;
-use crate::DeviceStatus;
+use crate::{DeviceStatus, FirmwareVersion};
 
 type Result<T> = std::result::Result<T, HeliosError>;
 
@@ -48,7 +48,12 @@ impl std::fmt::Debug for HeliosDacDevice {
 
 impl HeliosDacDevice {
     /// Writes and outputs a frame
+    ///
+    /// A frame without points stops the output instead, see [stop](HeliosDacDevice::stop).
     pub fn write_frame(&self, controller: &mut HeliosDacController, frame: Frame) -> Result<()> {
+        if frame.points.is_empty() {
+            return self.stop(controller);
+        }
         controller.wrapper.write_frame(self.device_number, frame)
     }
 
@@ -58,8 +63,8 @@ impl HeliosDacDevice {
     }
 
     /// Returns firmware version of DAC
-    pub fn firmware_version(&self, controller: &mut HeliosDacController) -> Result<u32> {
-        controller.wrapper.firmware_version(self.device_number)
+    pub fn firmware_version(&self, controller: &mut HeliosDacController) -> Result<FirmwareVersion> {
+        controller.wrapper.firmware_version(self.device_number).map(FirmwareVersion::from)
     }
 
     /// Sets name of DAC (name must be max 31 characters)
@@ -1,5 +1,10 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::iter::FromIterator;
+
 use bitflags::bitflags;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq)]
 pub struct Frame {
     /// Rate of output in points per second
@@ -10,6 +15,13 @@ pub struct Frame {
 }
 
 impl Frame {
+    /// Maximum number of points in a single frame
+    pub const MAX_POINTS: usize = 0x1000;
+    /// Maximum rate of output in points per second
+    pub const MAX_PPS: u32 = 0xFFFF;
+    /// Minimum rate of output in points per second
+    pub const MIN_PPS: u32 = 7;
+
     pub fn new(pps: u32, points: Vec<Point>) -> Self {
         Frame {
             pps,
@@ -25,8 +37,236 @@ impl Frame {
             flags
         }
     }
+
+    /// Blanked points added at the end of a clip and at the start of the next one by [concat](Frame::concat)
+    pub const CONCAT_TRAVEL_POINTS: usize = 8;
+
+    /// Joins clips into one frame, moving from the end of each clip to the start of the next with the laser off
+    ///
+    /// [CONCAT_TRAVEL_POINTS](Frame::CONCAT_TRAVEL_POINTS) blanked points are added at both ends of each
+    /// movement between clips. The frame uses the highest pps and the flags of the first clip,
+    /// clips without points are skipped. The result may contain more than [Frame::MAX_POINTS] points,
+    /// which the native dac can write with `write_frame_paced`.
+    pub fn concat(frames: &[Frame]) -> Frame {
+        let pps = frames.iter().map(|frame| frame.pps).max().unwrap_or(0);
+        let flags = frames.first().map(|frame| frame.flags).unwrap_or_else(WriteFrameFlags::empty);
+        let mut points: Vec<Point> = Vec::with_capacity(frames.iter().map(|frame| frame.points.len()).sum());
+        for frame in frames {
+            let (first, last) = match (frame.points.first(), points.last()) {
+                (None, _) => continue,
+                (Some(first), Some(last)) => (*first, *last),
+                (Some(_), None) => {
+                    points.extend_from_slice(&frame.points);
+                    continue;
+                }
+            };
+            for mut point in [last, first] {
+                point.blank();
+                points.extend(core::iter::repeat_n(point, Frame::CONCAT_TRAVEL_POINTS));
+            }
+            points.extend_from_slice(&frame.points);
+        }
+
+        Frame::new_with_flags(pps, points, flags)
+    }
+
+    /// Creates a frame drawn `fps` times per second, see [pps_for](Frame::pps_for)
+    #[cfg(feature = "std")]
+    pub fn with_fps(fps: f32, points: Vec<Point>) -> Self {
+        Frame::new(Frame::pps_for(points.len(), fps), points)
+    }
+
+    /// Rate of output needed to draw `points` points `fps` times per second
+    ///
+    /// The rate is clamped to the limits of the dac, use [achievable_fps](Frame::achievable_fps)
+    /// to check whether the target is reachable.
+    #[cfg(feature = "std")]
+    pub fn pps_for(points: usize, fps: f32) -> u32 {
+        let pps = (points as f32 * fps).round();
+
+        pps.clamp(Frame::MIN_PPS as f32, Frame::MAX_PPS as f32) as u32
+    }
+
+    /// Frames per second when drawing `points` points at `pps`, after clamping `pps` to the limits of the dac
+    pub fn achievable_fps(points: usize, pps: u32) -> f32 {
+        if points == 0 {
+            return f32::INFINITY;
+        }
+
+        pps.clamp(Frame::MIN_PPS, Frame::MAX_PPS) as f32 / points as f32
+    }
+
+    /// Creates a frame after checking it against the limits of the dac, see [validate](Frame::validate)
+    pub fn try_new(pps: u32, points: Vec<Point>) -> Result<Self, FrameError> {
+        let frame = Frame::new(pps, points);
+        frame.validate()?;
+
+        Ok(frame)
+    }
+
+    /// Checks the frame against the limits of the dac
+    pub fn validate(&self) -> Result<(), FrameError> {
+        if self.points.len() > Frame::MAX_POINTS {
+            return Err(FrameError::TooManyPoints(self.points.len()));
+        }
+        if self.pps > Frame::MAX_PPS {
+            return Err(FrameError::PpsTooHigh(self.pps));
+        }
+        if self.pps < Frame::MIN_PPS {
+            return Err(FrameError::PpsTooLow(self.pps));
+        }
+        let out_of_range = |point: &Point| point.coordinate.x > Coordinate::MAX || point.coordinate.y > Coordinate::MAX;
+        if let Some(point) = self.points.iter().position(out_of_range) {
+            return Err(FrameError::CoordinateOutOfRange {
+                point,
+                coordinate: self.points[point].coordinate,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects points into a frame with a pps of 0, which has to be set before the frame can be written
+impl FromIterator<Point> for Frame {
+    fn from_iter<I: IntoIterator<Item = Point>>(points: I) -> Self {
+        Frame::new(0, points.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Frame {
+    type Item = &'a Point;
+    type IntoIter = core::slice::Iter<'a, Point>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    /// Frame contains more than [Frame::MAX_POINTS] points
+    TooManyPoints(usize),
+    /// Rate of output is higher than [Frame::MAX_PPS]
+    PpsTooHigh(u32),
+    /// Rate of output is lower than [Frame::MIN_PPS]
+    PpsTooLow(u32),
+    /// Point at the index is outside of the coordinate space, see [Coordinate::MAX]
+    CoordinateOutOfRange { point: usize, coordinate: Coordinate },
+}
+
+impl core::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            FrameError::TooManyPoints(count) => write!(f, "frame contains {} points, max is {}", count, Frame::MAX_POINTS),
+            FrameError::PpsTooHigh(pps) => write!(f, "pps {} is higher than max of {}", pps, Frame::MAX_PPS),
+            FrameError::PpsTooLow(pps) => write!(f, "pps {} is lower than min of {}", pps, Frame::MIN_PPS),
+            FrameError::CoordinateOutOfRange { point, coordinate } => write!(
+                f,
+                "point {} at ({}, {}) is outside of the coordinate space",
+                point, coordinate.x, coordinate.y
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FrameError {}
+
+/// Points referencing their color in a shared palette
+///
+/// Each point is stored as `(x, y, palette index)`, expand it with [to_points](PaletteFrame::to_points) to write it to a dac.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaletteFrame {
+    pub palette: Vec<[u8; 3]>,
+    pub points: Vec<(u16, u16, u8)>,
+}
+
+impl PaletteFrame {
+    pub fn new(palette: Vec<[u8; 3]>, points: Vec<(u16, u16, u8)>) -> Self {
+        PaletteFrame { palette, points }
+    }
+
+    /// Looks up the color of each point, all points get max intensity
+    ///
+    /// Fails when a point references a color outside of the palette.
+    pub fn to_points(&self) -> Result<Vec<Point>, InvalidPaletteIndex> {
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(point, (x, y, index))| {
+                let [r, g, b] = *self.palette
+                    .get(usize::from(*index))
+                    .ok_or(InvalidPaletteIndex { point, index: *index })?;
+
+                Ok(Point {
+                    coordinate: Coordinate { x: *x, y: *y },
+                    color: Color::new(r, g, b),
+                    intensity: 0xFF,
+                })
+            })
+            .collect()
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPaletteIndex {
+    /// Index of the offending point
+    pub point: usize,
+    /// Palette index referenced by the point
+    pub index: u8,
+}
+
+impl core::fmt::Display for InvalidPaletteIndex {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "point {} references palette index {} which doesn't exist", self.point, self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidPaletteIndex {}
+
+/// Value passed to [Point::try_new] is out of range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointError {
+    /// Name of the offending argument
+    pub field: &'static str,
+    pub value: i32,
+    /// Highest allowed value, the lowest is always 0
+    pub max: i32,
+}
+
+impl core::fmt::Display for PointError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} is {}, expected 0 to {}", self.field, self.value, self.max)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PointError {}
+
+fn check<T, U>(value: T, field: &'static str, max: U) -> Result<U, PointError>
+where
+    T: Copy + Into<i32>,
+    U: Copy + Into<i32> + TryFrom<T>,
+{
+    let value_i32 = value.into();
+    match U::try_from(value) {
+        Ok(checked) if value_i32 >= 0 && value_i32 <= max.into() => Ok(checked),
+        _ => Err(PointError {
+            field,
+            value: value_i32,
+            max: max.into(),
+        }),
+    }
+}
+
+/// Single point of a frame
+///
+/// Prefer the accessors like [x](Point::x) and [rgb](Point::rgb) over the public fields,
+/// they stay the same when the way points are stored changes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point {
     pub coordinate: Coordinate,
@@ -34,21 +274,268 @@ pub struct Point {
     pub intensity: u8,
 }
 
+impl Point {
+    pub fn builder() -> PointBuilder {
+        PointBuilder::default()
+    }
+
+    /// Creates a point after checking every value, unlike the builder nothing is clamped
+    ///
+    /// Coordinates have to be within 0 and [Coordinate::MAX], color channels and intensity within 0 and 255.
+    pub fn try_new(x: i32, y: i32, r: u16, g: u16, b: u16, intensity: u16) -> Result<Self, PointError> {
+        Ok(Point {
+            coordinate: Coordinate {
+                x: check(x, "x", Coordinate::MAX)?,
+                y: check(y, "y", Coordinate::MAX)?,
+            },
+            color: Color::new(
+                check(r, "r", u8::MAX)?,
+                check(g, "g", u8::MAX)?,
+                check(b, "b", u8::MAX)?,
+            ),
+            intensity: check(intensity, "intensity", u8::MAX)?,
+        })
+    }
+
+    /// Creates a point with max intensity from coordinates in the range of -1.0 to 1.0
+    ///
+    /// -1.0 maps to 0 and 1.0 to [Coordinate::MAX], values outside of the range are clamped.
+    #[cfg(feature = "std")]
+    pub fn from_normalized(x: f32, y: f32, [r, g, b]: [u8; 3]) -> Self {
+        Point {
+            coordinate: Coordinate {
+                x: from_normalized(x),
+                y: from_normalized(y),
+            },
+            color: Color::new(r, g, b),
+            intensity: 0xFF,
+        }
+    }
+
+    /// Creates a point with max intensity from a color in hsv, see [hsv_to_rgb](crate::color::hsv_to_rgb)
+    #[cfg(feature = "std")]
+    pub fn from_hsv(x: u16, y: u16, h: f32, s: f32, v: f32) -> Self {
+        let [r, g, b] = crate::color::hsv_to_rgb(h, s, v);
+
+        Point {
+            coordinate: Coordinate { x, y },
+            color: Color::new(r, g, b),
+            intensity: 0xFF,
+        }
+    }
+
+    /// Inverse of [from_normalized](Point::from_normalized)
+    pub fn to_normalized(&self) -> (f32, f32, [u8; 3]) {
+        (
+            to_normalized(self.coordinate.x),
+            to_normalized(self.coordinate.y),
+            [self.color.r, self.color.g, self.color.b],
+        )
+    }
+
+    /// Horizontal position
+    pub fn x(&self) -> u16 {
+        self.coordinate.x
+    }
+
+    /// Vertical position
+    pub fn y(&self) -> u16 {
+        self.coordinate.y
+    }
+
+    /// Color channels as `[r, g, b]`
+    pub fn rgb(&self) -> [u8; 3] {
+        [self.color.r, self.color.g, self.color.b]
+    }
+
+    pub fn intensity(&self) -> u8 {
+        self.intensity
+    }
+
+    pub fn set_x(&mut self, x: u16) {
+        self.coordinate.x = x;
+    }
+
+    pub fn set_y(&mut self, y: u16) {
+        self.coordinate.y = y;
+    }
+
+    pub fn set_rgb(&mut self, [r, g, b]: [u8; 3]) {
+        self.color = Color::new(r, g, b);
+    }
+
+    pub fn set_intensity(&mut self, intensity: u8) {
+        self.intensity = intensity;
+    }
+
+    /// Whether the point emits no light, which is the case when its intensity or all of its color channels are zero
+    pub fn is_blanked(&self) -> bool {
+        self.intensity == 0 || self.color == Color::new(0, 0, 0)
+    }
+
+    /// Turns the point off by zeroing its color and intensity, the position is kept
+    pub fn blank(&mut self) {
+        self.color = Color::new(0, 0, 0);
+        self.intensity = 0;
+    }
+
+    /// Copy of the point at the same position [blanked](Point::blank)
+    pub fn blanked(mut self) -> Self {
+        self.blank();
+        self
+    }
+
+    /// Clamps both axes to [Coordinate::MAX], returns whether the point was out of bounds
+    ///
+    /// Frames with points outside of the coordinate space are rejected by [Frame::validate],
+    /// clamping moves such points onto the edge instead.
+    pub fn clamp_to_bounds(&mut self) -> bool {
+        let clamped = self.coordinate.x > Coordinate::MAX || self.coordinate.y > Coordinate::MAX;
+        self.coordinate.x = self.coordinate.x.min(Coordinate::MAX);
+        self.coordinate.y = self.coordinate.y.min(Coordinate::MAX);
+
+        clamped
+    }
+
+    /// Compares two points allowing each axis to differ by `coord_tol` and each color channel
+    /// as well as the intensity to differ by `color_tol`
+    ///
+    /// Useful to compare points after float round trips, the derived `PartialEq` stays exact.
+    pub fn approx_eq(&self, other: &Point, coord_tol: i16, color_tol: u8) -> bool {
+        let coord_tol = coord_tol.unsigned_abs();
+
+        self.coordinate.x.abs_diff(other.coordinate.x) <= coord_tol
+            && self.coordinate.y.abs_diff(other.coordinate.y) <= coord_tol
+            && self.color.r.abs_diff(other.color.r) <= color_tol
+            && self.color.g.abs_diff(other.color.g) <= color_tol
+            && self.color.b.abs_diff(other.color.b) <= color_tol
+            && self.intensity.abs_diff(other.intensity) <= color_tol
+    }
+
+    /// Whether both slices have the same length and all points are equal according to [approx_eq](Point::approx_eq)
+    pub fn all_approx_eq(a: &[Point], b: &[Point], coord_tol: i16, color_tol: u8) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, coord_tol, color_tol))
+    }
+
+    /// Mixes the color and intensity of `other` into this point, keeping the position of this point
+    ///
+    /// An `alpha` of 0.0 keeps the color of this point and 1.0 takes the color of `other`,
+    /// values outside of that range are clamped.
+    #[cfg(feature = "std")]
+    pub fn blend(&self, other: &Point, alpha: f32) -> Point {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * alpha).round() as u8;
+
+        Point {
+            coordinate: self.coordinate,
+            color: Color::new(
+                mix(self.color.r, other.color.r),
+                mix(self.color.g, other.color.g),
+                mix(self.color.b, other.color.b),
+            ),
+            intensity: mix(self.intensity, other.intensity),
+        }
+    }
+
+    /// Adds the color of `other` to this point, saturating each channel at 255
+    ///
+    /// The position and intensity of this point are kept.
+    pub fn add_color(&self, other: &Point) -> Point {
+        Point {
+            color: Color::new(
+                self.color.r.saturating_add(other.color.r),
+                self.color.g.saturating_add(other.color.g),
+                self.color.b.saturating_add(other.color.b),
+            ),
+            ..*self
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn from_normalized(value: f32) -> u16 {
+    ((value.clamp(-1.0, 1.0) + 1.0) / 2.0 * f32::from(Coordinate::MAX)).round() as u16
+}
+
+fn to_normalized(value: u16) -> f32 {
+    f32::from(value.min(Coordinate::MAX)) / f32::from(Coordinate::MAX) * 2.0 - 1.0
+}
+
+/// Builder for [Point]
+///
+/// Defaults to a black point at (0, 0) with max intensity.
+#[derive(Debug, Clone, Copy)]
+pub struct PointBuilder {
+    coordinate: Coordinate,
+    color: Color,
+    intensity: u8,
+}
+
+impl Default for PointBuilder {
+    fn default() -> Self {
+        PointBuilder {
+            coordinate: Coordinate { x: 0, y: 0 },
+            color: Color::new(0, 0, 0),
+            intensity: 0xFF,
+        }
+    }
+}
+
+impl PointBuilder {
+    /// Sets the coordinates, values above [Coordinate::MAX] are clamped
+    ///
+    /// Panics in debug builds when a coordinate is out of range.
+    pub fn position(mut self, x: u16, y: u16) -> Self {
+        debug_assert!(x <= Coordinate::MAX, "x coordinate {:#X} is out of range", x);
+        debug_assert!(y <= Coordinate::MAX, "y coordinate {:#X} is out of range", y);
+        self.coordinate = Coordinate {
+            x: x.min(Coordinate::MAX),
+            y: y.min(Coordinate::MAX),
+        };
+        self
+    }
+
+    pub fn rgb(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.color = Color::new(r, g, b);
+        self
+    }
+
+    pub fn intensity(mut self, intensity: u8) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn build(self) -> Point {
+        Point {
+            coordinate: self.coordinate,
+            color: self.color,
+            intensity: self.intensity,
+        }
+    }
+}
+
 /// Coordinates (x, y)
 ///
 /// 12 bit (from 0 to 0xFFF)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Coordinate {
     pub x: u16,
     pub y: u16
 }
 
+impl Coordinate {
+    /// Highest addressable value for both axes
+    pub const MAX: u16 = 0xFFF;
+}
+
 impl From<(u16, u16)> for Coordinate {
     fn from((x, y): (u16, u16)) -> Self {
         Coordinate { x, y }
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     /// Red channel
@@ -70,6 +557,7 @@ impl Color {
 }
 
 bitflags! {
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct WriteFrameFlags: u8 {
         /// Bit 0 (LSB) = if 1, start output immediately, instead of waiting for current frame (if there is one) to finish playing
         const START_IMMEDIATELY  = 0b0000_0001;
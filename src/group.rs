@@ -0,0 +1,266 @@
+use crate::{DeviceStatus, Frame, NativeHeliosDac, NativeHeliosDacController, NativeHeliosError, Point};
+
+type Result<T> = std::result::Result<T, NativeHeliosError>;
+
+/// Blanked points [DacGroup::write_split] adds where the path leaves and enters the part of a dac
+pub const SEAM_BLANK_POINTS: usize = 8;
+
+/// Multiple dacs playing content in sync
+///
+/// Errors are reported per dac so one failing unit doesn't stop the others.
+pub struct DacGroup {
+    dacs: Vec<NativeHeliosDac>,
+}
+
+impl DacGroup {
+    pub fn new(dacs: Vec<NativeHeliosDac>) -> Self {
+        DacGroup { dacs }
+    }
+
+    /// Opens all connected dacs
+    pub fn open_all(controller: &NativeHeliosDacController) -> Result<Self> {
+        let dacs = controller.list_devices()?
+            .into_iter()
+            .map(NativeHeliosDac::open)
+            .collect::<Result<_>>()?;
+
+        Ok(DacGroup { dacs })
+    }
+
+    /// Writes one frame to each dac, frames are paired with dacs in order
+    ///
+    /// All frames are encoded upfront so the transfers to the dacs happen as close together as possible.
+    pub fn write_frame_all(&mut self, frames: Vec<Frame>) -> Vec<Result<()>> {
+        let encoded = self.dacs
+            .iter()
+            .zip(frames)
+            .map(|(dac, frame)| dac.encode_frame(&frame))
+            .collect::<Vec<_>>();
+
+        self.dacs
+            .iter_mut()
+            .zip(encoded)
+            .map(|(dac, frame)| dac.send_frame(frame?))
+            .collect()
+    }
+
+    /// Splits one path across the dacs, `split` returns the index of the dac drawing a point
+    ///
+    /// Points with an index outside of the group are dropped. Wherever the path leaves the part of a dac
+    /// and enters it again, [SEAM_BLANK_POINTS] blanked points are added at the exit and at the entry,
+    /// so the beam doesn't draw along the seam. Each part is drawn at `pps` and checked against the limits
+    /// of its dac, dacs without any points are stopped.
+    pub fn write_split(&mut self, pps: u32, points: &[Point], split: impl Fn(&Point) -> usize) -> Vec<Result<()>> {
+        let mut parts = vec![Vec::new(); self.dacs.len()];
+        let mut last_index = vec![None; self.dacs.len()];
+        for (index, point) in points.iter().enumerate() {
+            let dac = split(point);
+            let part: &mut Vec<Point> = match parts.get_mut(dac) {
+                Some(part) => part,
+                None => continue,
+            };
+            if last_index[dac].is_some_and(|last| last + 1 != index) {
+                for mut seam in [part[part.len() - 1], *point] {
+                    seam.blank();
+                    part.extend(std::iter::repeat_n(seam, SEAM_BLANK_POINTS));
+                }
+            }
+            part.push(*point);
+            last_index[dac] = Some(index);
+        }
+        let encoded = self.dacs
+            .iter()
+            .zip(parts)
+            .map(|(dac, part)| (!part.is_empty()).then(|| dac.encode_frame(&Frame::new(pps, part))))
+            .collect::<Vec<_>>();
+
+        self.dacs
+            .iter_mut()
+            .zip(encoded)
+            .map(|(dac, frame)| match frame {
+                Some(frame) => dac.send_frame(frame?),
+                None => dac.stop(),
+            })
+            .collect()
+    }
+
+    pub fn status_all(&self) -> Vec<Result<DeviceStatus>> {
+        self.dacs.iter().map(NativeHeliosDac::status).collect()
+    }
+
+    /// Stops output of all dacs
+    pub fn stop_all(&self) -> Vec<Result<()>> {
+        self.dacs.iter().map(NativeHeliosDac::stop).collect()
+    }
+
+    pub fn dacs(&self) -> &[NativeHeliosDac] {
+        &self.dacs
+    }
+
+    pub fn dacs_mut(&mut self) -> &mut [NativeHeliosDac] {
+        &mut self.dacs
+    }
+
+    pub fn len(&self) -> usize {
+        self.dacs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dacs.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<NativeHeliosDac> {
+        self.dacs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Coordinate, MockDac};
+
+    fn group(count: usize) -> (Vec<MockDac>, DacGroup) {
+        let mocks = (0..count).map(|_| MockDac::new()).collect::<Vec<_>>();
+        let dacs = mocks
+            .iter()
+            .map(|mock| NativeHeliosDac::from_transport(mock.clone()).unwrap())
+            .collect();
+
+        (mocks, DacGroup::new(dacs))
+    }
+
+    fn points(count: usize) -> Vec<Point> {
+        (0..count)
+            .map(|i| Point {
+                coordinate: Coordinate { x: (i % 0x1000) as u16, y: 0x800 },
+                color: Color::new(255, 255, 255),
+                intensity: 255,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frames_are_paired_with_dacs_in_order() {
+        let (mocks, mut group) = group(2);
+
+        let results = group.write_frame_all(vec![Frame::new(30000, points(10)), Frame::new(20000, points(20))]);
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(mocks[0].points_written(), 10);
+        assert_eq!(mocks[0].frames()[0].pps, 30000);
+        assert_eq!(mocks[1].points_written(), 20);
+        assert_eq!(mocks[1].frames()[0].pps, 20000);
+    }
+
+    #[test]
+    fn errors_are_reported_per_dac() {
+        let (mocks, mut group) = group(3);
+        mocks[0].fail_frame_writes(1);
+
+        let results = group.write_frame_all(vec![
+            Frame::new(30000, points(10)),
+            Frame::new(30000, points(Frame::MAX_POINTS + 1)),
+            Frame::new(30000, points(10)),
+        ]);
+
+        assert!(matches!(results[0], Err(NativeHeliosError::UsbError(_))));
+        assert!(matches!(results[1], Err(NativeHeliosError::InvalidFrame(_))));
+        assert!(results[2].is_ok());
+        assert!(mocks[0].frames().is_empty());
+        assert!(mocks[1].frames().is_empty());
+        assert_eq!(mocks[2].points_written(), 10);
+    }
+
+    #[test]
+    fn status_and_stop_reach_every_dac() {
+        let (mocks, mut group) = group(2);
+        mocks[1].set_ready(false);
+        group.write_frame_all(vec![Frame::new(30000, points(10)), Frame::new(30000, points(10))]);
+
+        let statuses = group.status_all().into_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(statuses, vec![DeviceStatus::Ready, DeviceStatus::NotReady]);
+
+        assert!(group.stop_all().iter().all(Result::is_ok));
+        assert!(mocks.iter().all(MockDac::is_stopped));
+    }
+
+
+    fn line(xs: impl Iterator<Item = u16>) -> Vec<Point> {
+        xs.map(|x| Point::builder().position(x, 0x800).build()).collect()
+    }
+
+    fn by_half(point: &Point) -> usize {
+        usize::from(point.coordinate.x >= 2000)
+    }
+
+    #[test]
+    fn split_paths_are_drawn_by_the_dac_of_each_point() {
+        let (mocks, mut group) = group(2);
+        let path = line((0..4000).step_by(100));
+
+        let results = group.write_split(30000, &path, by_half);
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(mocks[0].frames()[0].points, path[..20]);
+        assert_eq!(mocks[1].frames()[0].points, path[20..]);
+        assert_eq!(mocks[0].frames()[0].pps, 30000);
+    }
+
+    #[test]
+    fn split_paths_are_blanked_where_they_leave_and_enter_a_part() {
+        let (mocks, mut group) = group(2);
+        let mut path = line((0..=1000).step_by(100));
+        path.extend(line((2000..=3000).step_by(100)));
+        path.extend(line((0..=900).rev().step_by(100)));
+
+        group.write_split(30000, &path, by_half);
+
+        let left = &mocks[0].frames()[0].points;
+        assert_eq!(left.len(), 11 + 2 * SEAM_BLANK_POINTS + 10);
+        assert_eq!(left[..11], path[..11]);
+        let (exit, entry) = left[11..11 + 2 * SEAM_BLANK_POINTS].split_at(SEAM_BLANK_POINTS);
+        assert!(exit.iter().all(|point| point.is_blanked() && point.coordinate.x == 1000));
+        assert!(entry.iter().all(|point| point.is_blanked() && point.coordinate.x == 900));
+        assert_eq!(left[11 + 2 * SEAM_BLANK_POINTS..], path[22..]);
+        // the right part is drawn in one go
+        assert_eq!(mocks[1].frames()[0].points, path[11..22]);
+    }
+
+    #[test]
+    fn dacs_without_points_are_stopped() {
+        let (mocks, mut group) = group(2);
+        group.write_frame_all(vec![Frame::new(30000, points(10)), Frame::new(30000, points(10))]);
+
+        let results = group.write_split(30000, &line((0..1000).step_by(100)), by_half);
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(!mocks[0].is_stopped());
+        assert!(mocks[1].is_stopped());
+        assert_eq!(mocks[1].frames().len(), 1);
+    }
+
+    #[test]
+    fn points_outside_of_the_group_are_dropped() {
+        let (mocks, mut group) = group(2);
+        let path = line((0..4000).step_by(100));
+
+        group.write_split(30000, &path, |point| if point.coordinate.x < 1000 { 0 } else { 5 });
+
+        assert_eq!(mocks[0].frames()[0].points, path[..10]);
+        assert!(mocks[1].frames().is_empty());
+    }
+
+    #[test]
+    fn split_parts_are_checked_against_the_limits_of_their_dac() {
+        let (mocks, mut group) = group(2);
+        let mut path = points(Frame::MAX_POINTS + 1);
+        path.push(Point::builder().position(0xFFF, 0).build());
+
+        let results = group.write_split(30000, &path, |point| usize::from(point.coordinate.y == 0));
+
+        assert!(matches!(results[0], Err(NativeHeliosError::InvalidFrame(_))));
+        assert!(results[1].is_ok());
+        assert!(mocks[0].frames().is_empty());
+        assert_eq!(mocks[1].points_written(), 1);
+    }
+}
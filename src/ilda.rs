@@ -0,0 +1,318 @@
+//! Reading and writing of [ILDA image data transfer format](https://www.ilda.com/resources/StandardsDocs/ILDA_IDTF14_rev011.pdf) files
+//!
+//! Reading supports the 3D and 2D formats with indexed (0, 1) and true color (4, 5) as well as color palettes (2).
+//! Writing always uses 2D true color (5).
+//! ILDA coordinates are mapped onto the 12 bit coordinate space of the dac.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use crate::frame::{Color, Coordinate, Point};
+
+const MAGIC: &[u8; 4] = b"ILDA";
+const HEADER_SIZE: usize = 32;
+
+const STATUS_LAST_POINT: u8 = 0b1000_0000;
+const STATUS_BLANKING: u8 = 0b0100_0000;
+
+/// Palette used by indexed formats until a palette record is read
+const DEFAULT_PALETTE: [[u8; 3]; 64] = [
+    [255, 0, 0], [255, 16, 0], [255, 32, 0], [255, 48, 0],
+    [255, 64, 0], [255, 80, 0], [255, 96, 0], [255, 112, 0],
+    [255, 128, 0], [255, 144, 0], [255, 160, 0], [255, 176, 0],
+    [255, 192, 0], [255, 208, 0], [255, 224, 0], [255, 240, 0],
+    [255, 255, 0], [224, 255, 0], [192, 255, 0], [160, 255, 0],
+    [128, 255, 0], [96, 255, 0], [64, 255, 0], [32, 255, 0],
+    [0, 255, 0], [0, 255, 36], [0, 255, 73], [0, 255, 109],
+    [0, 255, 146], [0, 255, 182], [0, 255, 219], [0, 255, 255],
+    [0, 227, 255], [0, 198, 255], [0, 170, 255], [0, 142, 255],
+    [0, 113, 255], [0, 85, 255], [0, 56, 255], [0, 28, 255],
+    [0, 0, 255], [32, 0, 255], [64, 0, 255], [96, 0, 255],
+    [128, 0, 255], [160, 0, 255], [192, 0, 255], [224, 0, 255],
+    [255, 0, 255], [255, 32, 255], [255, 64, 255], [255, 96, 255],
+    [255, 128, 255], [255, 160, 255], [255, 192, 255], [255, 224, 255],
+    [255, 255, 255], [255, 224, 224], [255, 192, 192], [255, 160, 160],
+    [255, 128, 128], [255, 96, 96], [255, 64, 64], [255, 32, 32],
+];
+
+/// Reads all frames of an ild file
+pub fn read_frames(reader: impl Read) -> io::Result<Vec<Vec<Point>>> {
+    FrameReader::new(reader).collect()
+}
+
+/// Writes frames as an ild file
+///
+/// Points which emit no light are written as blanked, see [Point::is_blanked].
+/// The format has no intensity, so it is multiplied into the colors.
+/// A header without points marks the end of an ild file, so frames without points are written
+/// as a single blanked point in the center.
+pub fn write_frames(mut writer: impl Write, frames: &[Vec<Point>]) -> io::Result<()> {
+    let total_frames = u16::try_from(frames.len())
+        .map_err(|_| invalid_input(format!("ilda files can't contain more than {} frames", u16::MAX)))?;
+
+    let empty = [Point {
+        coordinate: Coordinate {
+            x: Coordinate::MAX / 2,
+            y: Coordinate::MAX / 2,
+        },
+        color: Color::new(0, 0, 0),
+        intensity: 0,
+    }];
+    for (index, points) in frames.iter().enumerate() {
+        let points = if points.is_empty() { &empty[..] } else { &points[..] };
+        let record_count = u16::try_from(points.len())
+            .map_err(|_| invalid_input(format!("ilda frames can't contain more than {} points", u16::MAX)))?;
+        write_header(&mut writer, record_count, index as u16, total_frames)?;
+
+        let mut records = Vec::with_capacity(points.len() * 8);
+        for (index, point) in points.iter().enumerate() {
+            let mut status = 0;
+            if index == points.len() - 1 {
+                status |= STATUS_LAST_POINT;
+            }
+            let [r, g, b] = [point.color.r, point.color.g, point.color.b].map(|channel| with_intensity(channel, point.intensity));
+            if point.is_blanked() || [r, g, b] == [0, 0, 0] {
+                status |= STATUS_BLANKING;
+            }
+            records.extend_from_slice(&to_ilda_coordinate(point.coordinate.x).to_be_bytes());
+            records.extend_from_slice(&to_ilda_coordinate(point.coordinate.y).to_be_bytes());
+            records.extend_from_slice(&[status, b, g, r]);
+        }
+        writer.write_all(&records)?;
+    }
+    // a header without records marks the end of the file
+    write_header(&mut writer, 0, 0, total_frames)
+}
+
+fn with_intensity(channel: u8, intensity: u8) -> u8 {
+    ((u16::from(channel) * u16::from(intensity) + 127) / 255) as u8
+}
+
+fn write_header(writer: &mut impl Write, record_count: u16, frame_number: u16, total_frames: u16) -> io::Result<()> {
+    let mut header = [0u8; HEADER_SIZE];
+    header[0..4].copy_from_slice(MAGIC);
+    header[7] = 5;
+    header[24..26].copy_from_slice(&record_count.to_be_bytes());
+    header[26..28].copy_from_slice(&frame_number.to_be_bytes());
+    header[28..30].copy_from_slice(&total_frames.to_be_bytes());
+
+    writer.write_all(&header)
+}
+
+/// Reads frames of an ild file one at a time
+pub struct FrameReader<R> {
+    reader: R,
+    palette: Vec<[u8; 3]>,
+    done: bool,
+}
+
+struct Header {
+    format: u8,
+    record_count: usize,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        FrameReader {
+            reader,
+            palette: DEFAULT_PALETTE.to_vec(),
+            done: false,
+        }
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Vec<Point>>> {
+        loop {
+            let header = match self.read_header()? {
+                Some(header) if header.record_count > 0 => header,
+                _ => return Ok(None),
+            };
+
+            let record_size = match header.format {
+                0 | 5 => 8,
+                1 => 6,
+                2 => 3,
+                4 => 10,
+                format => return Err(invalid_data(format!("unsupported ilda format {}", format))),
+            };
+            let mut records = vec![0u8; header.record_count * record_size];
+            self.reader.read_exact(&mut records)?;
+            let records = records.chunks_exact(record_size);
+
+            let points = match header.format {
+                2 => {
+                    self.palette = records.map(|record| [record[0], record[1], record[2]]).collect();
+                    continue;
+                }
+                0 => records
+                    .map(|record| self.indexed_point(record, record[6], record[7]))
+                    .collect::<io::Result<_>>()?,
+                1 => records
+                    .map(|record| self.indexed_point(record, record[4], record[5]))
+                    .collect::<io::Result<_>>()?,
+                4 => records
+                    .map(|record| point(record, record[6], [record[9], record[8], record[7]]))
+                    .collect(),
+                _ => records
+                    .map(|record| point(record, record[4], [record[7], record[6], record[5]]))
+                    .collect(),
+            };
+
+            return Ok(Some(points));
+        }
+    }
+
+    fn read_header(&mut self) -> io::Result<Option<Header>> {
+        let mut header = [0u8; HEADER_SIZE];
+        let mut read = 0;
+        while read < HEADER_SIZE {
+            match self.reader.read(&mut header[read..]) {
+                // files without an end of file header just stop after the last frame
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(count) => read += count,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("missing ilda header".to_string()));
+        }
+
+        Ok(Some(Header {
+            format: header[7],
+            record_count: usize::from(u16::from_be_bytes([header[24], header[25]])),
+        }))
+    }
+
+    fn indexed_point(&self, record: &[u8], status: u8, index: u8) -> io::Result<Point> {
+        let color = self.palette
+            .get(usize::from(index))
+            .ok_or_else(|| invalid_data(format!("color index {} is not in palette", index)))?;
+
+        Ok(point(record, status, *color))
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<Vec<Point>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame().transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        frame
+    }
+}
+
+/// Builds a point from a record starting with the x and y coordinates
+fn point(record: &[u8], status: u8, [r, g, b]: [u8; 3]) -> Point {
+    let coordinate = Coordinate {
+        x: from_ilda_coordinate(i16::from_be_bytes([record[0], record[1]])),
+        y: from_ilda_coordinate(i16::from_be_bytes([record[2], record[3]])),
+    };
+    let mut point = Point {
+        coordinate,
+        color: Color::new(r, g, b),
+        intensity: 0xFF,
+    };
+    if status & STATUS_BLANKING == STATUS_BLANKING {
+        point.blank();
+    }
+
+    point
+}
+
+fn from_ilda_coordinate(value: i16) -> u16 {
+    ((i32::from(value) + 0x8000) >> 4) as u16
+}
+
+fn to_ilda_coordinate(value: u16) -> i16 {
+    ((i32::from(value.min(Coordinate::MAX)) << 4) - 0x8000) as i16
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16, [r, g, b]: [u8; 3], intensity: u8) -> Point {
+        Point {
+            coordinate: Coordinate { x, y },
+            color: Color::new(r, g, b),
+            intensity,
+        }
+    }
+
+    fn round_trip(frames: &[Vec<Point>]) -> (Vec<u8>, Vec<Vec<Point>>) {
+        let mut file = Vec::new();
+        write_frames(&mut file, frames).unwrap();
+        let read = read_frames(&file[..]).unwrap();
+
+        (file, read)
+    }
+
+    /// Status bytes of the records of the first frame
+    fn statuses(file: &[u8], records: usize) -> Vec<u8> {
+        (0..records).map(|index| file[HEADER_SIZE + index * 8 + 4]).collect()
+    }
+
+    #[test]
+    fn round_trips_coordinates_and_colors() {
+        let frame = vec![
+            point(0, 0, [255, 0, 0], 255),
+            point(Coordinate::MAX, Coordinate::MAX, [0, 255, 0], 255),
+            point(0, Coordinate::MAX, [1, 2, 3], 255),
+            point(0x123, 0xABC, [255, 255, 255], 255),
+        ];
+        let (_, frames) = round_trip(std::slice::from_ref(&frame));
+
+        assert_eq!(frames, vec![frame]);
+    }
+
+    #[test]
+    fn marks_last_point_and_blanked_points() {
+        let frame = vec![
+            point(0, 0, [255, 255, 255], 255),
+            point(1, 1, [0, 0, 0], 255),
+            point(2, 2, [255, 255, 255], 0),
+            point(3, 3, [255, 255, 255], 255),
+        ];
+        let (file, frames) = round_trip(&[frame]);
+
+        assert_eq!(statuses(&file, 4), vec![0, STATUS_BLANKING, STATUS_BLANKING, STATUS_LAST_POINT]);
+        let blanked: Vec<bool> = frames[0].iter().map(Point::is_blanked).collect();
+        assert_eq!(blanked, vec![false, true, true, false]);
+    }
+
+    #[test]
+    fn multiplies_intensity_into_colors() {
+        let (_, frames) = round_trip(&[vec![point(0, 0, [204, 102, 51], 10)]]);
+
+        assert_eq!(frames[0][0].color, Color::new(8, 4, 2));
+    }
+
+    #[test]
+    fn writes_empty_frames_as_a_blanked_point() {
+        let lit = vec![point(0, 0, [255, 255, 255], 255)];
+        let (file, frames) = round_trip(&[lit.clone(), Vec::new(), lit.clone()]);
+
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], lit);
+        assert_eq!(frames[1], vec![point(Coordinate::MAX / 2, Coordinate::MAX / 2, [0, 0, 0], 0)]);
+        assert_eq!(frames[2], lit);
+        // the end of file header follows the last frame
+        assert_eq!(file.len(), 4 * HEADER_SIZE + 3 * 8);
+    }
+}
@@ -1,21 +1,118 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 #[cfg(feature = "sdk")]
 pub mod wrapper;
 #[cfg(feature = "sdk")]
 mod device;
 #[cfg(feature = "native")]
 mod native;
+#[cfg(feature = "native")]
+mod mock;
+#[cfg(feature = "native")]
+mod playback;
+#[cfg(feature = "native")]
+mod group;
+#[cfg(feature = "async")]
+mod async_native;
 mod frame;
+#[cfg(feature = "std")]
+pub mod transform;
+#[cfg(feature = "std")]
+pub mod ilda;
+#[cfg(feature = "std")]
+pub mod path;
+#[cfg(feature = "std")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod safety;
+#[cfg(feature = "std")]
+pub mod calibration;
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod shapes;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod show;
+#[cfg(feature = "native")]
+pub mod stats;
+#[cfg(feature = "native")]
+pub mod record;
+#[cfg(all(feature = "image", feature = "std"))]
+pub mod raster;
 
 #[cfg(feature = "sdk")]
 pub use crate::{device::*, wrapper::HeliosError};
 #[cfg(feature = "native")]
-pub use crate::native::*;
+pub use crate::{native::*, mock::MockDac, playback::*, group::{DacGroup, SEAM_BLANK_POINTS}};
+#[cfg(feature = "async")]
+pub use crate::async_native::*;
 pub use crate::frame::*;
 
-#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DeviceStatus {
     /// Device is ready to receive frame
     Ready = 1,
     /// Device is not ready to receive frame
     NotReady = 0
 }
+
+/// Firmware version reported by a dac
+///
+/// Helios firmware is versioned with a single increasing number, so versions compare by it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion(u32);
+
+impl FirmwareVersion {
+    pub const fn new(version: u32) -> Self {
+        FirmwareVersion(version)
+    }
+
+    /// Raw version number as reported by the dac
+    pub fn number(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for FirmwareVersion {
+    fn from(version: u32) -> Self {
+        FirmwareVersion(version)
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+impl DeviceStatus {
+    /// Whether the device can receive the next frame
+    ///
+    /// The dac only reports this flag, it has no notion of buffer fullness beyond the next frame.
+    pub fn ready(&self) -> bool {
+        matches!(self, DeviceStatus::Ready)
+    }
+
+    /// Whether the device can receive a frame with `points` points
+    ///
+    /// A ready device accepts any frame up to [Frame::MAX_POINTS] points.
+    pub fn can_accept(&self, points: usize) -> bool {
+        self.ready() && points <= Frame::MAX_POINTS
+    }
+
+    /// Amount of points the device can receive with the next frame
+    pub fn space_available(&self) -> usize {
+        if self.ready() {
+            Frame::MAX_POINTS
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{DeviceStatus, Frame, NativeHeliosError, Transport};
+use crate::protocol::{Command, ReadFromBytes, Response, WriteToBytes};
+
+type Result<T> = std::result::Result<T, NativeHeliosError>;
+
+/// In-memory dac answering like real hardware
+///
+/// Clones share their state so a copy handed to [NativeHeliosDac::from_transport](crate::NativeHeliosDac::from_transport)
+/// can be inspected afterwards.
+#[derive(Clone)]
+pub struct MockDac {
+    state: Arc<Mutex<MockState>>,
+}
+
+struct MockState {
+    name: String,
+    firmware_version: u32,
+    ready: bool,
+    stopped: bool,
+    shutter_open: bool,
+    sdk_version: Option<u8>,
+    frames: Vec<Frame>,
+    responses: VecDeque<Vec<u8>>,
+    failing_frame_writes: usize,
+    holding_responses: bool,
+}
+
+impl MockDac {
+    pub fn new() -> Self {
+        MockDac {
+            state: Arc::new(Mutex::new(MockState {
+                name: String::from("Mock"),
+                firmware_version: 6,
+                ready: true,
+                stopped: true,
+                shutter_open: false,
+                sdk_version: None,
+                frames: Vec::new(),
+                responses: VecDeque::new(),
+                failing_frame_writes: 0,
+                holding_responses: false,
+            })),
+        }
+    }
+
+    /// Frames received by the dac
+    pub fn frames(&self) -> Vec<Frame> {
+        self.state().frames.clone()
+    }
+
+    /// Total amount of points received by the dac
+    pub fn points_written(&self) -> usize {
+        self.state().frames.iter().map(|frame| frame.points.len()).sum()
+    }
+
+    /// Whether output was stopped since the last frame
+    pub fn is_stopped(&self) -> bool {
+        self.state().stopped
+    }
+
+    /// Whether the shutter was last opened or closed
+    pub fn is_shutter_open(&self) -> bool {
+        self.state().shutter_open
+    }
+
+    /// Sdk version sent during the handshake
+    pub fn sdk_version(&self) -> Option<u8> {
+        self.state().sdk_version
+    }
+
+    /// Sets the status reported to the host
+    pub fn set_ready(&self, ready: bool) {
+        self.state().ready = ready;
+    }
+
+    pub fn set_name(&self, name: &str) {
+        self.state().name = name.to_string();
+    }
+
+    pub fn set_firmware_version(&self, version: u32) {
+        self.state().firmware_version = version;
+    }
+
+    /// Makes the next `count` frame transfers fail like on a dac which was unplugged
+    pub fn fail_frame_writes(&self, count: usize) {
+        self.state().failing_frame_writes = count;
+    }
+
+    /// Keeps answers to control messages back until released, like a dac which is slow to respond
+    pub fn hold_responses(&self, hold: bool) {
+        self.state().holding_responses = hold;
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for MockDac {
+    fn default() -> Self {
+        MockDac::new()
+    }
+}
+
+impl Transport for MockDac {
+    fn write_frame(&self, buffer: &[u8], _: Duration) -> Result<usize> {
+        let frame = Frame::read_from_bytes(buffer).map_err(|_| NativeHeliosError::InvalidDeviceResult)?;
+        let mut state = self.state();
+        if state.failing_frame_writes > 0 {
+            state.failing_frame_writes -= 1;
+            return Err(rusb::Error::Io.into());
+        }
+        state.stopped = false;
+        state.frames.push(frame);
+
+        Ok(buffer.len())
+    }
+
+    fn write_control(&self, buffer: &[u8], _: Duration) -> Result<usize> {
+        let mut state = self.state();
+        let response = match Command::read_from_bytes(buffer) {
+            Ok(Command::Stop) => {
+                state.stopped = true;
+                None
+            }
+            Ok(Command::GetStatus) => {
+                let status = if state.ready { DeviceStatus::Ready } else { DeviceStatus::NotReady };
+                Some(Response::Status(status))
+            }
+            Ok(Command::GetFirmwareVersion) => Some(Response::FirmwareVersion(state.firmware_version.into())),
+            Ok(Command::GetName) => Some(Response::Name(state.name.clone())),
+            Ok(Command::SetName(name)) => {
+                state.name = name;
+                None
+            }
+            Ok(Command::SendSdkVersion(version)) => {
+                state.sdk_version = Some(version);
+                None
+            }
+            Ok(Command::SetShutter(open)) => {
+                state.shutter_open = open;
+                None
+            }
+            Err(_) => None,
+        };
+        if let Some(response) = response {
+            state.responses.push_back(response.to_bytes());
+        }
+
+        Ok(buffer.len())
+    }
+
+    fn read_control(&self, buffer: &mut [u8], _: Duration) -> Result<usize> {
+        let mut state = self.state();
+        if state.holding_responses {
+            return Err(rusb::Error::Timeout.into());
+        }
+        let response = state.responses.pop_front().ok_or(rusb::Error::Timeout)?;
+        let size = response.len().min(buffer.len());
+        buffer[..size].copy_from_slice(&response[..size]);
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Coordinate, NativeHeliosDac, Point};
+
+    fn points(count: u16) -> Vec<Point> {
+        (0..count)
+            .map(|i| Point {
+                coordinate: Coordinate { x: i, y: 0xFFF - i },
+                color: Color::new(i as u8, 0, 255),
+                intensity: 255,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn answers_the_handshake() {
+        let mock = MockDac::new();
+        mock.set_name("Projector");
+        mock.set_firmware_version(5);
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        assert!(mock.sdk_version().is_some());
+        assert_eq!(dac.name().unwrap(), "Projector");
+        assert_eq!(dac.firmware_version().unwrap().number(), 5);
+        assert_eq!(dac.fingerprint().unwrap().name, "Projector");
+    }
+
+    #[test]
+    fn records_frames_with_their_points_and_rate() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        assert!(mock.is_stopped());
+
+        dac.write_frame(Frame::new(12_345, points(10))).unwrap();
+        dac.write_frame(Frame::new(30_000, points(20))).unwrap();
+
+        let frames = mock.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].pps, 12_345);
+        assert_eq!(frames[0].points, points(10));
+        assert_eq!(frames[1].pps, 30_000);
+        assert_eq!(mock.points_written(), 30);
+        assert!(!mock.is_stopped());
+
+        dac.stop().unwrap();
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn reports_the_status_it_was_set_to() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        assert_eq!(dac.status().unwrap(), DeviceStatus::Ready);
+        mock.set_ready(false);
+        assert_eq!(dac.status().unwrap(), DeviceStatus::NotReady);
+    }
+
+    #[test]
+    fn tracks_shutter_and_name() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        assert!(!mock.is_shutter_open());
+        dac.set_shutter(true).unwrap();
+        assert!(mock.is_shutter_open());
+        dac.set_shutter(false).unwrap();
+        assert!(!mock.is_shutter_open());
+
+        dac.set_name("Renamed").unwrap();
+        assert_eq!(dac.name().unwrap(), "Renamed");
+    }
+
+    #[test]
+    fn clones_share_their_state() {
+        let mock = MockDac::new();
+        let clone = mock.clone();
+        clone.set_ready(false);
+        let dac = NativeHeliosDac::from_transport(clone).unwrap();
+
+        assert_eq!(dac.status().unwrap(), DeviceStatus::NotReady);
+        mock.set_ready(true);
+        assert_eq!(dac.status().unwrap(), DeviceStatus::Ready);
+    }
+
+    #[test]
+    fn fails_the_requested_amount_of_frame_writes() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.fail_frame_writes(2);
+
+        for _ in 0..2 {
+            let result = dac.write_frame(Frame::new(30_000, points(10)));
+            assert!(matches!(result, Err(NativeHeliosError::UsbError(rusb::Error::Io))));
+        }
+        dac.write_frame(Frame::new(30_000, points(10))).unwrap();
+        assert_eq!(mock.frames().len(), 1);
+    }
+
+    #[test]
+    fn rejects_transfers_which_are_not_frames() {
+        let mock = MockDac::new();
+
+        assert!(mock.write_frame(&[0, 1, 2], Duration::from_millis(10)).is_err());
+        assert!(mock.frames().is_empty());
+    }
+
+    #[test]
+    fn reads_time_out_without_a_pending_response() {
+        let mock = MockDac::new();
+        let mut buffer = [0u8; 32];
+
+        let result = mock.read_control(&mut buffer, Duration::from_millis(10));
+        assert!(matches!(result, Err(NativeHeliosError::Timeout)));
+    }
+}
@@ -1,8 +1,24 @@
-use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 use rusb::{Context, Device, UsbContext};
 use thiserror::Error;
-use crate::{Frame, DeviceStatus};
+use crate::{Coordinate, Frame, FrameError, DeviceStatus, FirmwareVersion, Point, WriteFrameFlags};
+use crate::calibration::Calibration;
+use crate::transform::CoordinateSpace;
+use crate::protocol::{
+    self, Command, ProtocolError, Response, WriteToBytes, FRAME_FOOTER_SIZE, POINT_SIZE, RESPONSE_GET_FIRMWARE_VERSION,
+    RESPONSE_GET_NAME, RESPONSE_GET_STATUS,
+};
+use crate::safety::{self, SafetyLimiter, SafetyLimitExceeded};
+use crate::show::{self, ValidationIssue};
+use crate::scheduler::InvalidFps;
+use crate::stats::StreamStats;
+
+pub use crate::protocol::MAX_NAME_LENGTH;
 
 type Result<T> = std::result::Result<T, NativeHeliosError>;
 
@@ -11,27 +27,30 @@ const SDK_VERSION: u8 = 6;
 const HELIOS_VID: u16 = 0x1209;
 const HELIOS_PID: u16 = 0xE500;
 
-const HELIOS_MAX_POINTS: u32 = 0x1000;
-const HELIOS_MAX_RATE: u32 = 0xFFFF;
-const HELIOS_MIN_RATE: u32 = 7;
+const CONTROL_TIMEOUT: Duration = Duration::from_millis(16);
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(32);
+
+// smallest timeout libusb accepts, zero waits forever
+const POLL_TIMEOUT: Duration = Duration::from_millis(1);
+const STREAM_CHUNK_DURATION: Duration = Duration::from_millis(20);
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Time [NativeHeliosDac::shutdown] waits for the dac to be ready after the final blanked frame
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Time [NativeHeliosDac::stream_points] waits for the dac to be ready after the last chunk should have been drawn
+pub const STREAM_READY_MARGIN: Duration = Duration::from_millis(100);
 
-const FRAME_BUFFER_SIZE: u32 = HELIOS_MAX_POINTS * 7 + 5;
+/// Time [NativeHeliosDac::wait_until_drawn] waits for the dac after the points should have been drawn
+pub const DRAW_ONCE_MARGIN: Duration = Duration::from_millis(20);
+
+const FRAME_BUFFER_SIZE: usize = Frame::MAX_POINTS * POINT_SIZE + FRAME_FOOTER_SIZE;
 
 // Interrupt endpoints
 const ENDPOINT_BULK_OUT: u8 = 0x02;
-const ENDPOINT_BULK_IN: u8 = 0x81;
 const ENDPOINT_INT_OUT: u8 = 0x06;
 const ENDPOINT_INT_IN: u8 = 0x83;
 
-// Interrupt control bytes
-const CONTROL_STOP: u8 = 0x01;
-const CONTROL_SET_SHUTTER: u8 = 0x02;
-const CONTROL_GET_STATUS: u8 = 0x03;
-const CONTROL_GET_FIRMWARE_VERSION: u8 = 0x04;
-const CONTROL_GET_NAME: u8 = 0x05;
-const CONTROL_SET_NAME: u8 = 0x06;
-const CONTROL_SEND_SDK_VERSION: u8 = 0x07;
-
 pub struct NativeHeliosDacController {
     context: rusb::Context,
 }
@@ -43,11 +62,18 @@ impl NativeHeliosDacController {
         })
     }
 
+    /// Scans the usb bus for connected dacs.
+    ///
+    /// Devices which enumerate but can't be queried are skipped instead of failing the whole scan.
+    /// Returns an empty list when no dac is connected.
     pub fn list_devices(&self) -> Result<Vec<NativeHeliosDac>> {
         let devices = self.context.devices()?;
         let mut dacs = vec![];
         for device in devices.iter() {
-            let descriptor = device.device_descriptor()?;
+            let descriptor = match device.device_descriptor() {
+                Ok(descriptor) => descriptor,
+                Err(_) => continue,
+            };
             if descriptor.vendor_id() != HELIOS_VID ||
                 descriptor.product_id() != HELIOS_PID {
                 continue;
@@ -58,172 +84,1683 @@ impl NativeHeliosDacController {
 
         Ok(dacs)
     }
+
+    /// Opens the first connected dac
+    pub fn open_first(&self) -> Result<NativeHeliosDac> {
+        self.open_by_index(0)
+    }
+
+    /// Opens the dac at `index` in the order returned by [list_devices](NativeHeliosDacController::list_devices)
+    pub fn open_by_index(&self, index: usize) -> Result<NativeHeliosDac> {
+        let dac = self.list_devices()?
+            .into_iter()
+            .nth(index)
+            .ok_or(NativeHeliosError::DeviceNotFound)?;
+
+        dac.open()
+    }
+}
+
+/// Configures a dac before its handshake, see [NativeHeliosDac::builder]
+#[derive(Debug, Clone)]
+pub struct NativeHeliosDacBuilder {
+    buffer_capacity: Option<usize>,
+    timeout: Option<Duration>,
+    stop_on_drop: bool,
+    reconnect_retries: u8,
+}
+
+impl Default for NativeHeliosDacBuilder {
+    fn default() -> Self {
+        NativeHeliosDacBuilder {
+            buffer_capacity: None,
+            timeout: None,
+            stop_on_drop: true,
+            reconnect_retries: 0,
+        }
+    }
+}
+
+impl NativeHeliosDacBuilder {
+    /// Amount of points the frame buffer is sized for, frames with more points still grow it
+    ///
+    /// By default the buffer is sized for [Frame::MAX_POINTS] points.
+    /// Hints above that are ignored as the dac can't receive larger frames.
+    pub fn buffer_capacity_hint(mut self, points: usize) -> Self {
+        self.buffer_capacity = Some(points);
+        self
+    }
+
+    /// See [NativeHeliosDac::set_timeout]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [NativeHeliosDac::set_stop_on_drop]
+    pub fn auto_stop_on_drop(mut self, stop_on_drop: bool) -> Self {
+        self.stop_on_drop = stop_on_drop;
+        self
+    }
+
+    /// See [NativeHeliosDac::with_auto_reconnect]
+    pub fn auto_reconnect(mut self, retries: u8) -> Self {
+        self.reconnect_retries = retries;
+        self
+    }
+
+    /// Applies the settings to `dac` and opens it
+    pub fn build(&self, mut dac: NativeHeliosDac) -> Result<NativeHeliosDac> {
+        if let Some(points) = self.buffer_capacity {
+            if points > Frame::MAX_POINTS {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(points, max_points = Frame::MAX_POINTS, "buffer capacity hint exceeds the dac");
+            }
+            dac.frame_buffer_size = points.min(Frame::MAX_POINTS) * POINT_SIZE + FRAME_FOOTER_SIZE;
+        }
+        dac.timeout = self.timeout;
+        dac.stop_on_drop = self.stop_on_drop;
+        dac.reconnect_retries = self.reconnect_retries;
+
+        dac.open()
+    }
+
+    /// Opens the first dac found by `controller`, see [NativeHeliosDacController::open_first]
+    pub fn build_first(&self, controller: &NativeHeliosDacController) -> Result<NativeHeliosDac> {
+        self.build_by_index(controller, 0)
+    }
+
+    /// Opens the dac at `index`, see [NativeHeliosDacController::open_by_index]
+    pub fn build_by_index(&self, controller: &NativeHeliosDacController, index: usize) -> Result<NativeHeliosDac> {
+        let dac = controller.list_devices()?
+            .into_iter()
+            .nth(index)
+            .ok_or(NativeHeliosError::DeviceNotFound)?;
+
+        self.build(dac)
+    }
 }
 
-pub enum NativeHeliosDac {
+/// Raw transfers to a dac
+///
+/// Implemented for libusb device handles.
+/// Implement it to talk to a dac over another connection, see [MockDac](crate::MockDac) for an in-memory implementation.
+pub trait Transport: Send + Sync {
+    /// Sends an encoded frame
+    fn write_frame(&self, buffer: &[u8], timeout: Duration) -> Result<usize>;
+
+    /// Sends a control message
+    fn write_control(&self, buffer: &[u8], timeout: Duration) -> Result<usize>;
+
+    /// Reads the response to the last control message
+    fn read_control(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize>;
+}
+
+impl Transport for rusb::DeviceHandle<rusb::Context> {
+    fn write_frame(&self, buffer: &[u8], timeout: Duration) -> Result<usize> {
+        Ok(self.write_bulk(ENDPOINT_BULK_OUT, buffer, timeout)?)
+    }
+
+    fn write_control(&self, buffer: &[u8], timeout: Duration) -> Result<usize> {
+        Ok(self.write_interrupt(ENDPOINT_INT_OUT, buffer, timeout)?)
+    }
+
+    fn read_control(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        Ok(self.read_interrupt(ENDPOINT_INT_IN, buffer, timeout)?)
+    }
+}
+
+/// Dac talking the helios usb protocol
+///
+/// **An opened dac stops its output when it is dropped**, so the laser goes dark when the program exits or panics.
+/// Use [set_stop_on_drop](NativeHeliosDac::set_stop_on_drop) to keep the last frame playing instead.
+pub struct NativeHeliosDac {
+    state: DacState,
+    safety_limiter: Option<SafetyLimiter>,
+    safety_scale: Option<f32>,
+    calibration: Option<Calibration>,
+    coordinate_space: Option<CoordinateSpace>,
+    watchdog: Option<Watchdog>,
+    /// Timeout of the watchdog, kept while it is stopped for a reconnect
+    watchdog_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    location: Option<UsbLocation>,
+    fingerprint: Option<DeviceFingerprint>,
+    accept_device_swap: bool,
+    reconnect_retries: u8,
+    status_pending: AtomicBool,
+    stop_on_drop: bool,
+    frame_buffer: Vec<u8>,
+    frame_buffer_size: usize,
+    stats: StreamStats,
+    /// When the last frame written in single mode should be drawn completely
+    single_frame_end: Option<Instant>,
+}
+
+enum DacState {
     Idle(rusb::Device<rusb::Context>),
-    Open {
-        device: rusb::Device<rusb::Context>,
-        handle: rusb::DeviceHandle<rusb::Context>,
-    },
+    Open(Arc<rusb::DeviceHandle<rusb::Context>>),
+    Transport(Arc<dyn Transport>),
 }
 
 impl NativeHeliosDac {
-    pub fn open(self) -> Result<Self> {
-        match self {
-            NativeHeliosDac::Idle(device) => {
-                let mut handle = device.open()?;
-                handle.claim_interface(0)?;
-                handle.set_alternate_setting(0, 1)?;
-                let device = NativeHeliosDac::Open {
-                    device,
-                    handle,
-                };
+    fn new(state: DacState) -> Self {
+        NativeHeliosDac {
+            state,
+            safety_limiter: None,
+            safety_scale: None,
+            calibration: None,
+            coordinate_space: None,
+            watchdog: None,
+            watchdog_timeout: None,
+            timeout: None,
+            location: None,
+            fingerprint: None,
+            accept_device_swap: false,
+            reconnect_retries: 0,
+            status_pending: AtomicBool::new(false),
+            stop_on_drop: true,
+            frame_buffer: Vec::new(),
+            frame_buffer_size: FRAME_BUFFER_SIZE,
+            stats: StreamStats::default(),
+            single_frame_end: None,
+        }
+    }
+
+    /// Creates an opened dac talking over the given transport
+    pub fn from_transport(transport: impl Transport + 'static) -> Result<Self> {
+        let mut dac = NativeHeliosDac::new(DacState::Transport(Arc::new(transport)));
+        dac.handshake()?;
+        dac.fingerprint = Some(dac.read_fingerprint()?);
 
-                let _ = device.firmware_version()?;
-                device.send_sdk_version()?;
+        Ok(dac)
+    }
 
-                Ok(device)
+    pub fn is_open(&self) -> bool {
+        !matches!(self.state, DacState::Idle(_))
+    }
+
+    pub fn open(mut self) -> Result<Self> {
+        self.connect()?;
+
+        Ok(self)
+    }
+
+    /// Opens the same usb device again after the connection was lost
+    ///
+    /// The dac is looked up by its bus and port, so it has to be plugged back into the same port.
+    /// All settings of this dac are kept and a running watchdog is restarted,
+    /// also when an earlier reconnect failed.
+    ///
+    /// When the dac found at that port has a different name or firmware version, another dac was plugged in.
+    /// The handshake has already run on that dac by then, which reads its firmware version and name
+    /// and sends the sdk version. It is closed again before any frame is written and
+    /// [NativeHeliosError::DeviceMismatch] is returned,
+    /// unless swaps are accepted with [set_accept_device_swap](NativeHeliosDac::set_accept_device_swap).
+    ///
+    /// A dac created [from a transport](NativeHeliosDac::from_transport) runs the handshake on the same transport again,
+    /// as the transport can't be closed it stays open after a mismatch.
+    pub fn reconnect(&mut self) -> Result<()> {
+        let state = match &self.state {
+            DacState::Transport(transport) => DacState::Transport(Arc::clone(transport)),
+            _ => {
+                let location = self.location.as_ref().ok_or(NativeHeliosError::DeviceNotFound)?;
+                let context = Context::new()?;
+                let device = context.devices()?
+                    .iter()
+                    .find(|device| location.matches(device))
+                    .ok_or(NativeHeliosError::DeviceNotFound)?;
+                DacState::Idle(device)
+            }
+        };
+
+        // the watchdog holds on to the old handle, it is started again once the dac is connected
+        self.watchdog = None;
+        let expected = self.fingerprint.take();
+        // dropping the old handle closes it
+        self.state = state;
+        let connected = match self.state {
+            DacState::Transport(_) => self.identify(),
+            _ => self.connect(),
+        };
+        if let (Some(expected), Some(found)) = (&expected, &self.fingerprint) {
+            if !self.accept_device_swap && expected != found {
+                let found = found.clone();
+                self.disconnect();
+                self.fingerprint = Some(expected.clone());
+                return Err(NativeHeliosError::DeviceMismatch {
+                    expected: expected.clone(),
+                    found,
+                });
+            }
+        }
+        if connected.is_err() {
+            self.fingerprint = expected;
+        }
+        connected?;
+        if let Some(timeout) = self.watchdog_timeout {
+            self.start_watchdog(timeout)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether [reconnect](NativeHeliosDac::reconnect) accepts a different dac plugged into the same port,
+    /// disabled by default
+    pub fn set_accept_device_swap(&mut self, accept: bool) {
+        self.accept_device_swap = accept;
+    }
+
+    /// Name and firmware version read when the dac was opened
+    pub fn fingerprint(&self) -> Option<&DeviceFingerprint> {
+        self.fingerprint.as_ref()
+    }
+
+    /// Settings to apply before opening a dac
+    pub fn builder() -> NativeHeliosDacBuilder {
+        NativeHeliosDacBuilder::default()
+    }
+
+    /// Reconnects up to `retries` times when writing a frame fails because the connection was lost
+    ///
+    /// The frame is sent again after a successful reconnect.
+    /// When another dac was plugged in the retries end right away with [NativeHeliosError::DeviceMismatch].
+    pub fn with_auto_reconnect(mut self, retries: u8) -> Self {
+        self.reconnect_retries = retries;
+        self
+    }
+
+    fn connect(&mut self) -> Result<()> {
+        let mut handle = match &self.state {
+            DacState::Idle(device) => device.open()?,
+            _ => return Ok(()),
+        };
+        handle.claim_interface(0)?;
+        if let Err(err) = handle.set_alternate_setting(0, 1) {
+            let _ = handle.release_interface(0);
+            return Err(err.into());
+        }
+        self.state = DacState::Open(Arc::new(handle));
+
+        self.identify()
+    }
+
+    /// Runs the handshake and reads the fingerprint of a connected dac, disconnects it when that fails
+    fn identify(&mut self) -> Result<()> {
+        match self.handshake().and_then(|_| self.read_fingerprint()) {
+            Ok(fingerprint) => {
+                self.fingerprint = Some(fingerprint);
+                Ok(())
+            }
+            Err(err) => {
+                self.disconnect();
+                Err(err)
             }
-            open => Ok(open)
+        }
+    }
+
+    /// Closes an opened usb device and releases its interface so it can be opened again
+    fn disconnect(&mut self) {
+        if let DacState::Open(handle) = &mut self.state {
+            let device = handle.device();
+            if let Some(handle) = Arc::get_mut(handle) {
+                let _ = handle.release_interface(0);
+            }
+            self.state = DacState::Idle(device);
+        }
+    }
+
+    fn read_fingerprint(&self) -> Result<DeviceFingerprint> {
+        Ok(DeviceFingerprint {
+            name: self.name()?,
+            firmware_version: self.firmware_version()?,
+        })
+    }
+
+    /// Limits the power of all following frames, see [SafetyLimiter]
+    pub fn set_safety_limiter(&mut self, limiter: Option<SafetyLimiter>) {
+        self.safety_limiter = limiter;
+        self.safety_scale = None;
+    }
+
+    /// Remaps the coordinates of all following frames to correct the distortion of the projector
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+    }
+
+    pub fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Reads the coordinates of all following frames as positions in `space`, see [CoordinateSpace::remap]
+    ///
+    /// Coordinates above [Coordinate::MAX](crate::Coordinate::MAX) are accepted then.
+    /// Frames are processed in this order: the safety limiter scales the colors, then the coordinates are
+    /// converted from `space` and clamped to the coordinate space of the dac, then the calibration is applied.
+    pub fn set_coordinate_space(&mut self, space: Option<CoordinateSpace>) {
+        self.coordinate_space = space;
+    }
+
+    pub fn coordinate_space(&self) -> Option<&CoordinateSpace> {
+        self.coordinate_space.as_ref()
+    }
+
+    /// Factor the colors of the last frame were scaled by to satisfy the safety limiter
+    pub fn safety_scale(&self) -> Option<f32> {
+        self.safety_scale
+    }
+
+    /// Overrides the timeout of all usb transfers
+    ///
+    /// By default each transfer uses a timeout matching its size, 16ms for commands and 32ms for responses.
+    /// `None` restores these defaults. A stalled or unplugged dac fails with [NativeHeliosError::Timeout].
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Whether output is stopped when this dac is dropped, enabled by default
+    pub fn set_stop_on_drop(&mut self, stop_on_drop: bool) {
+        self.stop_on_drop = stop_on_drop;
+    }
+
+    /// Settings chosen for this dac, to set up another dac the same way with [apply_config](NativeHeliosDac::apply_config)
+    pub fn config(&self) -> DacConfig {
+        DacConfig {
+            safety_limiter: self.safety_limiter,
+            calibration: self.calibration,
+            coordinate_space: self.coordinate_space,
+            timeout: self.timeout,
+            reconnect_retries: self.reconnect_retries,
+            stop_on_drop: self.stop_on_drop,
+            accept_device_swap: self.accept_device_swap,
+        }
+    }
+
+    /// Replaces the settings of this dac with `config`, see [config](NativeHeliosDac::config)
+    pub fn apply_config(&mut self, config: &DacConfig) {
+        self.set_safety_limiter(config.safety_limiter);
+        self.calibration = config.calibration;
+        self.coordinate_space = config.coordinate_space;
+        self.timeout = config.timeout;
+        self.reconnect_retries = config.reconnect_retries;
+        self.stop_on_drop = config.stop_on_drop;
+        self.accept_device_swap = config.accept_device_swap;
+    }
+
+    /// Stops output when no command was sent to the dac for `timeout`
+    ///
+    /// Every successful command resets the timer, use [feed_watchdog](NativeHeliosDac::feed_watchdog) to reset it manually.
+    /// Replaces a previously started watchdog.
+    pub fn start_watchdog(&mut self, timeout: Duration) -> Result<()> {
+        let transport = self.shared_transport()?;
+        self.watchdog = Some(Watchdog::start(transport, timeout));
+        self.watchdog_timeout = Some(timeout);
+
+        Ok(())
+    }
+
+    /// Stops the watchdog thread
+    pub fn stop_watchdog(&mut self) {
+        self.watchdog = None;
+        self.watchdog_timeout = None;
+    }
+
+    /// Resets the watchdog timer
+    pub fn feed_watchdog(&self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.feed();
+        }
+    }
+
+    fn handshake(&self) -> Result<()> {
+        let _ = self.firmware_version()?;
+        self.send_sdk_version()
+    }
+
+    /// Maximum rate of output in points per second, see [Frame::MAX_PPS]
+    pub fn max_pps(&self) -> u32 {
+        Frame::MAX_PPS
+    }
+
+    /// Maximum number of points in a single frame, see [Frame::MAX_POINTS]
+    pub fn max_points(&self) -> usize {
+        Frame::MAX_POINTS
+    }
+
+    /// Checks a frame against everything [write_frame](NativeHeliosDac::write_frame) would reject it for
+    ///
+    /// These are the limits of the dac and the per point limit of the safety limiter.
+    pub fn validate_frame(&self, frame: &Frame) -> Result<()> {
+        self.check_limits(frame)?;
+        if let Some(limiter) = &self.safety_limiter {
+            limiter.scale_for(&frame.points)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks a frame against the limits of the dac, coordinates are only checked without a coordinate space
+    /// as they are clamped while remapping them
+    fn check_limits(&self, frame: &Frame) -> Result<()> {
+        match frame.validate() {
+            Err(FrameError::CoordinateOutOfRange { .. }) if self.coordinate_space.is_some() => Ok(()),
+            result => Ok(result?),
+        }
+    }
+
+    /// Checks every frame of a show drawn at `fps` and returns all issues, see [show::validate_show]
+    ///
+    /// Also reports points the safety limiter of the dac would reject.
+    pub fn validate_show(&self, frames: &[Vec<Point>], fps: f32) -> Vec<ValidationIssue> {
+        match &self.safety_limiter {
+            Some(limiter) => show::validate_show_with_limiter(frames, fps, limiter),
+            None => show::validate_show(frames, fps),
         }
     }
 
     /// writes and outputs a frame to the dac
+    ///
+    /// Fails early when the frame exceeds the limits of the dac (see [Frame::validate]).
+    /// When a safety limiter is set the colors are scaled down to stay below its limits.
+    /// The frame repeats until the next one is written, unless its flags contain [WriteFrameFlags::SINGLE_MODE],
+    /// see [wait_until_drawn](NativeHeliosDac::wait_until_drawn).
+    ///
+    /// A frame without points blanks the output by sending [stop](NativeHeliosDac::stop),
+    /// which is the intended way to turn the laser off while keeping the dac open.
+    ///
+    /// Every frame is sent in a single transfer, including frames of exactly [Frame::MAX_POINTS] points.
+    /// Frames of 45 + n * 64 points are sent without their last point and a slightly lower pps,
+    /// as the dac doesn't receive transfers of these sizes correctly.
     pub fn write_frame(&mut self, frame: Frame) -> Result<()> {
-        if let NativeHeliosDac::Open { handle, .. } = self {
-            let mut frame_buffer = Vec::new();
+        self.write_frame_ref(&frame)
+    }
+
+    /// Same as [write_frame](NativeHeliosDac::write_frame) but borrows the frame
+    ///
+    /// Frames are encoded into a buffer owned by the dac. It is sized for the largest possible frame on the first write
+    /// and reused afterwards, so following writes don't allocate.
+    pub fn write_frame_ref(&mut self, frame: &Frame) -> Result<()> {
+        if frame.points.is_empty() {
+            self.single_frame_end = None;
+            return self.stop();
+        }
+        let mut buffer = std::mem::take(&mut self.frame_buffer);
+        let result = self.encode_frame_into(frame, &mut buffer)
+            .and_then(|safety_scale| self.send_frame_buffer(&buffer, safety_scale));
+        self.frame_buffer = buffer;
+        if result.is_ok() {
+            self.single_frame_end = frame.flags.contains(WriteFrameFlags::SINGLE_MODE).then(|| {
+                Instant::now() + Duration::from_secs_f32(frame.points.len() as f32 / frame.pps as f32)
+            });
+        }
+
+        result
+    }
+
+    /// Writes a frame while raising its rate of output from a tenth of its pps to its pps over `ramp`
+    ///
+    /// The frame is sent again with a higher pps whenever the dac is ready, so the scanners speed up gradually.
+    /// Returns `true` once the frame was sent with its own pps, which happens no later than `ramp` after the start.
+    ///
+    /// Setting `cancel`, for example from another thread, interrupts the ramp within about a millisecond.
+    /// The output is stopped then and `false` returned.
+    pub fn write_frame_with_ramp(&mut self, mut frame: Frame, ramp: Duration, cancel: &AtomicBool) -> Result<bool> {
+        self.check_limits(&frame)?;
+        let target = frame.pps;
+        let start_pps = (target / 10).max(Frame::MIN_PPS);
+        let start = Instant::now();
+        let deadline = start + ramp;
+        while Instant::now() < deadline && !cancel.load(Ordering::SeqCst) {
+            let progress = start.elapsed().as_secs_f32() / ramp.as_secs_f32();
+            frame.pps = start_pps + (target.saturating_sub(start_pps) as f32 * progress) as u32;
+            self.write_frame_ref(&frame)?;
+            while !self.status()?.ready() && Instant::now() < deadline && !cancel.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        if cancel.load(Ordering::SeqCst) {
+            self.stop()?;
+            return Ok(false);
+        }
+        frame.pps = target;
+        self.write_frame(frame)?;
+
+        Ok(true)
+    }
+
+    /// Draws a static frame until [release](NativeHeliosDac::release) is called
+    ///
+    /// The dac repeats the last frame it received by itself, so this returns right after writing the frame
+    /// and nothing keeps running in the background. Re-sending the frame from a keep-alive thread would only
+    /// add usb traffic. A running [watchdog](NativeHeliosDac::start_watchdog) still stops the output
+    /// when it isn't fed, as the dac can't tell a held frame from a hung program.
+    pub fn hold_frame(&mut self, points: &[Point], pps: u32) -> Result<()> {
+        self.write_frame(Frame::new(pps, points.to_vec()))
+    }
+
+    /// Ends drawing a frame held by [hold_frame](NativeHeliosDac::hold_frame) by stopping the output
+    pub fn release(&mut self) -> Result<()> {
+        self.stop()
+    }
+
+    /// Draws a frame which may contain more than [Frame::MAX_POINTS] points once, the output stops afterwards
+    ///
+    /// Frames within the limit are written in [single mode](WriteFrameFlags::SINGLE_MODE).
+    /// Larger frames are drawn by [stream_points](NativeHeliosDac::stream_points),
+    /// which sends consecutive chunks of the points whenever the dac is ready, so no point is left out or repeated.
+    pub fn write_frame_paced(&mut self, mut frame: Frame) -> Result<()> {
+        if frame.points.len() <= Frame::MAX_POINTS {
+            frame.flags |= WriteFrameFlags::SINGLE_MODE;
+            return self.write_frame(frame);
+        }
+
+        self.stream_points(frame.points, frame.pps)
+    }
+
+    /// Streams a path which is too long for a single frame
+    ///
+    /// The points are sent in chunks of about 20ms whenever the dac is ready for the next one.
+    /// Chunks are written in [single mode](WriteFrameFlags::SINGLE_MODE), so the output goes dark instead of repeating
+    /// old points when the iterator can't keep up, and stops after the last point.
+    /// Fails with [NativeHeliosError::Timeout] when the dac isn't ready within [STREAM_READY_MARGIN]
+    /// after a chunk should have been drawn.
+    pub fn stream_points(&mut self, points: impl IntoIterator<Item = Point>, pps: u32) -> Result<()> {
+        let chunk_size = (pps as f32 * STREAM_CHUNK_DURATION.as_secs_f32()).ceil() as usize;
+        let chunk_size = chunk_size.clamp(1, Frame::MAX_POINTS);
+        let timeout = Duration::from_secs_f32(chunk_size as f32 / pps.max(Frame::MIN_PPS) as f32) + STREAM_READY_MARGIN;
+        let mut points = points.into_iter();
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let mut carry = None;
+        loop {
+            chunk.extend(carry.take());
+            chunk.extend(points.by_ref().take(chunk_size - chunk.len()));
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            // the last point would be dropped by the size workaround, it starts the next chunk instead
+            if drops_last_point(chunk.len()) {
+                carry = chunk.pop();
+            }
+            let frame = Frame::new_with_flags(pps, chunk, WriteFrameFlags::SINGLE_MODE);
+            if !self.wait_until_ready(timeout)? {
+                return Err(NativeHeliosError::Timeout);
+            }
+            self.write_frame_ref(&frame)?;
+            chunk = frame.points;
+            chunk.clear();
+        }
+    }
 
-            // this is a bug workaround, the mcu won't correctly receive transfers with these sizes
-            let mut pps_actual = frame.pps;
-            let mut num_of_points_actual = frame.points.len();
-            if (frame.points.len() - 45) % 64 == 0 {
-                num_of_points_actual -= 1;
-                // adjust pps to keep the same frame duration even with one less point
-                pps_actual = frame.pps * ((num_of_points_actual as f32) / (frame.points.len() as f32) + 0.5f32) as u32;
+    /// Polls the status until the dac is ready for the next frame, returns `false` when `timeout` passed first
+    ///
+    /// Being ready only means the dac can take another frame, it isn't proof that the current frame was drawn.
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.status()?.ready() {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
             }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Waits until the last frame written in [single mode](WriteFrameFlags::SINGLE_MODE) was drawn,
+    /// returns `false` when the dac isn't ready within [DRAW_ONCE_MARGIN] afterwards
+    ///
+    /// The dac doesn't report when it finished drawing, so this waits the time the points take at the pps
+    /// of the frame and then for the dac to be [ready](NativeHeliosDac::wait_until_ready).
+    /// When the last frame repeats there is nothing to wait for and only the margin applies.
+    pub fn wait_until_drawn(&self) -> Result<bool> {
+        if let Some(remaining) = self.single_frame_end.and_then(|end| end.checked_duration_since(Instant::now())) {
+            thread::sleep(remaining);
+        }
+
+        self.wait_until_ready(DRAW_ONCE_MARGIN)
+    }
+
+    /// Draws the points exactly once at `pps` and stops the output afterwards
+    ///
+    /// The points are sent in [single mode](WriteFrameFlags::SINGLE_MODE), replacing the current frame right away,
+    /// and the output is stopped once [wait_until_drawn](NativeHeliosDac::wait_until_drawn) returns.
+    pub fn draw_points_once(&mut self, points: &[Point], pps: u32) -> Result<()> {
+        let frame = Frame::new_with_flags(
+            pps,
+            points.to_vec(),
+            WriteFrameFlags::SINGLE_MODE | WriteFrameFlags::START_IMMEDIATELY,
+        );
+        self.write_frame_ref(&frame)?;
+        if frame.points.is_empty() {
+            return Ok(());
+        }
+        let result = self.wait_until_drawn();
+        self.stop()?;
 
-            for point in frame.points {
-                frame_buffer.push((point.coordinate.x >> 4) as u8);
-                frame_buffer.push(((point.coordinate.x & 0x0F) << 4) as u8 | (point.coordinate.y >> 8) as u8);
-                frame_buffer.push((point.coordinate.y & 0xFF) as u8);
-                frame_buffer.push(point.color.r);
-                frame_buffer.push(point.color.g);
-                frame_buffer.push(point.color.b);
-                frame_buffer.push(point.intensity);
+        result.map(|_| ())
+    }
+
+    /// Validates and encodes a frame without sending it
+    pub(crate) fn encode_frame(&self, frame: &Frame) -> Result<EncodedFrame> {
+        let mut buffer = Vec::new();
+        let safety_scale = self.encode_frame_into(frame, &mut buffer)?;
+
+        Ok(EncodedFrame {
+            buffer,
+            safety_scale,
+        })
+    }
+
+    /// Replaces the content of `buffer` with the encoded frame, returns the factor the colors were scaled by
+    fn encode_frame_into(&self, frame: &Frame, buffer: &mut Vec<u8>) -> Result<Option<f32>> {
+        self.check_limits(frame)?;
+        let safety_scale = match &self.safety_limiter {
+            Some(limiter) => Some(limiter.scale_for(&frame.points)?),
+            None => None,
+        };
+        buffer.clear();
+        buffer.reserve(self.frame_buffer_size);
+
+        // this is a bug workaround, the mcu won't correctly receive transfers with these sizes,
+        // the last point is dropped so the transfer is one point shorter
+        let mut pps_actual = frame.pps;
+        let mut num_of_points_actual = frame.points.len();
+        if drops_last_point(frame.points.len()) {
+            num_of_points_actual -= 1;
+            // adjust pps to keep the same frame duration even with one less point
+            pps_actual = (frame.pps as f32 * num_of_points_actual as f32 / frame.points.len() as f32 + 0.5) as u32;
+        }
+
+        for point in &frame.points[..num_of_points_actual] {
+            let mut point = *point;
+            if let Some(scale) = safety_scale {
+                safety::scale_point(&mut point, scale);
+            }
+            if let Some(space) = &self.coordinate_space {
+                space.remap(std::slice::from_mut(&mut point));
+            }
+            if let Some(calibration) = &self.calibration {
+                point.coordinate = calibration.apply(point.coordinate);
             }
-            frame_buffer.push((pps_actual & 0xFF) as u8);
-            frame_buffer.push((pps_actual >> 8) as u8);
-            frame_buffer.push((num_of_points_actual & 0xFF) as u8);
-            frame_buffer.push((num_of_points_actual >> 8) as u8);
-            frame_buffer.push(0); // flags
+            point.write_to_bytes(buffer);
+        }
+        protocol::write_frame_footer(buffer, pps_actual, num_of_points_actual, frame.flags);
+
+        Ok(safety_scale)
+    }
 
-            let timeout = (8 + frame_buffer.len() >> 5) as u64;
-            handle.write_bulk(ENDPOINT_BULK_OUT, &frame_buffer[0..], Duration::from_millis(timeout))?;
+    /// Sends a frame previously encoded by [encode_frame](NativeHeliosDac::encode_frame)
+    pub(crate) fn send_frame(&mut self, frame: EncodedFrame) -> Result<()> {
+        self.send_frame_buffer(&frame.buffer, frame.safety_scale)
+    }
 
-            Ok(())
-        }else {
-            Err(NativeHeliosError::DeviceNotOpened)
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(size = buffer.len())))]
+    fn send_frame_buffer(&mut self, buffer: &[u8], safety_scale: Option<f32>) -> Result<()> {
+        let mut result = self.transfer_frame(buffer);
+        let mut retries = 0;
+        while retries < self.reconnect_retries && result.as_ref().is_err_and(connection_lost) {
+            retries += 1;
+            thread::sleep(RECONNECT_DELAY);
+            match self.reconnect() {
+                Ok(()) => result = self.transfer_frame(buffer),
+                // reconnecting again would only open the other dac again
+                Err(err @ NativeHeliosError::DeviceMismatch { .. }) => return Err(err),
+                Err(_) => {}
+            }
         }
+        result?;
+        self.safety_scale = safety_scale;
+        self.stats.record(buffer.len().saturating_sub(FRAME_FOOTER_SIZE) / POINT_SIZE, Instant::now());
+        self.feed_watchdog();
+
+        Ok(())
+    }
+
+    /// Frames and points written since the dac was created or [reset_stats](NativeHeliosDac::reset_stats) was called
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = StreamStats::default();
+    }
+
+    fn transfer_frame(&self, buffer: &[u8]) -> Result<()> {
+        let timeout = self.timeout.unwrap_or(Duration::from_millis(((8 + buffer.len()) >> 5) as u64));
+        self.transport()?.write_frame(buffer, timeout)?;
+
+        Ok(())
     }
 
     /// Gets name of dac
     pub fn name(&self) -> Result<String> {
-        let ctrl_buffer = [CONTROL_GET_NAME, 0];
-        let (buffer, _) = self.call_control(&ctrl_buffer)?;
+        match self.call(&Command::GetName, RESPONSE_GET_NAME)? {
+            Response::Name(name) => Ok(name),
+            _ => Err(NativeHeliosError::InvalidDeviceResult),
+        }
+    }
 
-        match buffer {
-            [0x85, bytes @ ..] => {
-                let null_byte_position = bytes.iter().position(|b| *b == 0u8).unwrap_or(31); // max length is 30 chars
-                let (bytes_until_null, _) = bytes.split_at(null_byte_position);
-                let name = String::from_utf8(bytes_until_null.to_vec())?;
+    /// Sets name of dac, the name is stored persistently on the dac
+    ///
+    /// Fails with [NativeHeliosError::NameTooLong] for names longer than [MAX_NAME_LENGTH] bytes.
+    /// The [fingerprint](NativeHeliosDac::fingerprint) is updated, so [reconnect](NativeHeliosDac::reconnect)
+    /// still recognizes the renamed dac.
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        if name.len() > MAX_NAME_LENGTH {
+            return Err(NativeHeliosError::NameTooLong(name.len()));
+        }
 
-                Ok(name)
-            }
-            _ => Err(NativeHeliosError::InvalidDeviceResult)
+        self.send_control(&Command::SetName(name.to_string()))?;
+        if let Some(fingerprint) = &mut self.fingerprint {
+            fingerprint.name = name.to_string();
         }
+
+        Ok(())
     }
 
     /// Get firmware version
-    pub fn firmware_version(&self) -> Result<u32> {
-        let ctrl_buffer = [CONTROL_GET_FIRMWARE_VERSION, 0];
-        let (buffer, size) = self.call_control(&ctrl_buffer)?;
+    pub fn firmware_version(&self) -> Result<FirmwareVersion> {
+        match self.call(&Command::GetFirmwareVersion, RESPONSE_GET_FIRMWARE_VERSION)? {
+            Response::FirmwareVersion(version) => Ok(version),
+            _ => Err(NativeHeliosError::InvalidDeviceResult),
+        }
+    }
+
+    fn send_sdk_version(&self) -> Result<()> {
+        self.send_control(&Command::SendSdkVersion(SDK_VERSION))
+    }
 
-        match &buffer[0..size] {
-            [0x84, buffer @ ..] => {
-                let buffer = buffer.iter().map(|byte| u32::from(*byte)).collect::<Vec<_>>();
-                let version = (buffer[0] << 0) | (buffer[1] << 8) | (buffer[2] << 16) | (buffer[3] << 24);
+    /// Gets status of DAC
+    ///
+    /// Blocks until the dac answers or the response times out after 32ms, no allocations are made.
+    pub fn status(&self) -> Result<DeviceStatus> {
+        let (buffer, size) = if self.status_pending.swap(false, Ordering::SeqCst) {
+            self.read_response()?
+        } else {
+            self.call_control(&Command::GetStatus)?
+        };
+
+        parse_status(&buffer[0..size])
+    }
+
+    /// Amount of points the dac can receive right now, see [DeviceStatus::space_available]
+    pub fn space_available(&self) -> Result<usize> {
+        Ok(self.status()?.space_available())
+    }
+
+    /// Measures the time the dac takes to answer a command
+    ///
+    /// The dac has no dedicated ping command, so this times a status request, which changes no state on the dac.
+    /// An answer to an earlier [try_status](NativeHeliosDac::try_status) is dropped first so it isn't timed.
+    /// No allocations are made.
+    pub fn ping_roundtrip(&self) -> Result<Duration> {
+        if self.status_pending.swap(false, Ordering::SeqCst) {
+            let _ = self.read_response();
+        }
+        let start = Instant::now();
+        let (buffer, size) = self.call_control(&Command::GetStatus)?;
+        let round_trip = start.elapsed();
+        parse_status(&buffer[0..size])?;
+
+        Ok(round_trip)
+    }
 
-                Ok(version)
+    /// Gets status of DAC without waiting for the answer
+    ///
+    /// The first call requests the status, following calls return `None` until the answer arrived.
+    /// Each call waits at most 1ms for the answer.
+    pub fn try_status(&self) -> Result<Option<DeviceStatus>> {
+        if !self.status_pending.load(Ordering::SeqCst) {
+            self.send_control(&Command::GetStatus)?;
+            self.status_pending.store(true, Ordering::SeqCst);
+        }
+        let mut buffer: [u8; 32] = [0; 32];
+        match self.transport()?.read_control(&mut buffer, POLL_TIMEOUT) {
+            Ok(size) => {
+                self.status_pending.store(false, Ordering::SeqCst);
+                parse_status(&buffer[0..size.min(buffer.len())]).map(Some)
+            }
+            Err(NativeHeliosError::Timeout) => Ok(None),
+            Err(err) => {
+                self.status_pending.store(false, Ordering::SeqCst);
+                Err(err)
             }
-            _ => Err(NativeHeliosError::InvalidDeviceResult)
         }
     }
 
-    fn send_sdk_version(&self) -> Result<()> {
-        let ctrl_buffer = [CONTROL_SEND_SDK_VERSION, SDK_VERSION];
-        self.send_control(&ctrl_buffer)
+    /// Opens or closes the shutter of the dac without changing the current frame
+    ///
+    /// The dac doesn't report the state of its shutter, so it can't be read back.
+    pub fn set_shutter(&self, open: bool) -> Result<()> {
+        self.send_control(&Command::SetShutter(open))
     }
 
-    pub fn status(&self) -> Result<DeviceStatus> {
-        let ctrl_buffer = [CONTROL_GET_STATUS, 0];
-        let (buffer, size) = self.call_control(&ctrl_buffer)?;
+    /// Reads name, firmware version and status of the dac to compare them with a later state
+    pub fn snapshot(&self) -> Result<DacSnapshot> {
+        Ok(DacSnapshot {
+            name: self.name()?,
+            firmware_version: self.firmware_version()?,
+            status: self.status()?,
+        })
+    }
+
+    /// Reads the details of the dac into one printable struct, e.g. for logs and bug reports
+    pub fn device_info(&self) -> Result<DeviceInfo> {
+        Ok(DeviceInfo {
+            name: self.name()?,
+            firmware_version: self.firmware_version()?,
+            max_points: self.max_points(),
+            max_pps: self.max_pps(),
+            status: self.status()?,
+        })
+    }
 
-        match &buffer[0..size] {
-            [0x83, 0] => Ok(DeviceStatus::NotReady),
-            [0x83, 1] => Ok(DeviceStatus::Ready),
-            _ => Err(NativeHeliosError::InvalidDeviceResult)
+    /// Reads and discards unread responses until the dac has none left, returns the amount of discarded responses
+    ///
+    /// This resynchronizes commands and responses after they got out of step, e.g. after a response timed out
+    /// but arrived later. Only call it between commands, as it also discards the answer to a pending
+    /// [try_status](NativeHeliosDac::try_status).
+    pub fn flush(&self) -> Result<usize> {
+        self.status_pending.store(false, Ordering::SeqCst);
+        let mut buffer: [u8; 32] = [0; 32];
+        let mut discarded = 0;
+        loop {
+            match self.transport()?.read_control(&mut buffer, POLL_TIMEOUT) {
+                Ok(_) => discarded += 1,
+                Err(NativeHeliosError::Timeout) => return Ok(discarded),
+                Err(err) => return Err(err),
+            }
         }
     }
 
     /// Stops output of DAC
     pub fn stop(&self) -> Result<()> {
-        let ctrl_buffer = [CONTROL_STOP, 0];
-        self.send_control(&ctrl_buffer)
+        self.send_control(&Command::Stop)
     }
 
-    fn call_control(&self, buffer: &[u8]) -> Result<([u8; 32], usize)> {
-        self.send_control(buffer)?;
-        self.read_response()
+    /// Stops the output, draws a final blanked frame, waits for the dac to be ready and closes the dac
+    ///
+    /// The blanked frame is a single point in [single mode](WriteFrameFlags::SINGLE_MODE) which replaces
+    /// whatever the dac still had queued, the output is stopped again once the dac took it.
+    /// Unlike dropping the dac this reports whether the output was stopped, so the caller can make sure
+    /// the laser is dark. Fails with [NativeHeliosError::Timeout] when the dac doesn't report being ready
+    /// within [SHUTDOWN_TIMEOUT]. Works the same on a dac which is stopped already.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_watchdog();
+        self.stop()?;
+        let blank = Point::builder().position(Coordinate::MAX / 2, Coordinate::MAX / 2).intensity(0).build();
+        self.write_frame(Frame::new_with_flags(
+            Frame::MAX_PPS,
+            vec![blank],
+            WriteFrameFlags::SINGLE_MODE | WriteFrameFlags::START_IMMEDIATELY,
+        ))?;
+        if !self.wait_until_ready(SHUTDOWN_TIMEOUT)? {
+            return Err(NativeHeliosError::Timeout);
+        }
+        self.stop()?;
+        self.stop_on_drop = false;
+        self.disconnect();
+
+        Ok(())
     }
 
-    fn send_control(&self, buffer: &[u8]) -> Result<()> {
-        if let NativeHeliosDac::Open { handle, .. } = self {
-            let written_length = handle.write_interrupt(ENDPOINT_INT_OUT, buffer, Duration::from_millis(16))?;
-            assert_eq!(written_length, buffer.len());
+    /// Sends a command and decodes its response, which has to start with `expected`
+    fn call(&self, command: &Command, expected: u8) -> Result<Response> {
+        let (buffer, size) = self.call_control(command)?;
+
+        expect_response(&buffer[0..size], expected)
+    }
 
-            Ok(())
-        }else {
-            Err(NativeHeliosError::DeviceNotOpened)
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(?command)))]
+    fn call_control(&self, command: &Command) -> Result<([u8; 32], usize)> {
+        if self.status_pending.swap(false, Ordering::SeqCst) {
+            // drop the answer of an earlier try_status so it isn't mistaken for the answer to this command
+            let _ = self.read_response();
         }
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        self.send_control(command)?;
+        let response = self.read_response();
+        #[cfg(feature = "tracing")]
+        tracing::trace!(round_trip = ?start.elapsed(), "received response");
+
+        response
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(?command)))]
+    fn send_control(&self, command: &Command) -> Result<()> {
+        let (buffer, size) = protocol::encode_command(command);
+        let written_length = self.transport()?.write_control(&buffer[0..size], self.timeout.unwrap_or(CONTROL_TIMEOUT))?;
+        if written_length != size {
+            return Err(rusb::Error::Io.into());
+        }
+        self.feed_watchdog();
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(size)))]
     fn read_response(&self) -> Result<([u8; 32], usize)> {
-        if let NativeHeliosDac::Open { handle, .. } = self {
-            let mut buffer: [u8; 32] = [0; 32];
-            let size = handle.read_interrupt(ENDPOINT_INT_IN, &mut buffer, Duration::from_millis(32))?;
+        let mut buffer: [u8; 32] = [0; 32];
+        let size = self.transport()?.read_control(&mut buffer, self.timeout.unwrap_or(RESPONSE_TIMEOUT))?;
+        // a misbehaving transport could report more bytes than fit into the buffer
+        let size = size.min(buffer.len());
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("size", size);
+
+        Ok((buffer, size))
+    }
+
+    fn transport(&self) -> Result<&dyn Transport> {
+        match &self.state {
+            DacState::Idle(_) => Err(NativeHeliosError::DeviceNotOpened),
+            DacState::Open(handle) => Ok(handle.as_ref()),
+            DacState::Transport(transport) => Ok(transport.as_ref()),
+        }
+    }
+
+    fn shared_transport(&self) -> Result<Arc<dyn Transport>> {
+        match &self.state {
+            DacState::Idle(_) => Err(NativeHeliosError::DeviceNotOpened),
+            DacState::Open(handle) => Ok(Arc::clone(handle) as Arc<dyn Transport>),
+            DacState::Transport(transport) => Ok(Arc::clone(transport)),
+        }
+    }
+}
+
+/// Position of a dac on the usb bus, stays the same when it is plugged back into the same port
+struct UsbLocation {
+    bus: u8,
+    ports: Vec<u8>,
+}
+
+impl UsbLocation {
+    fn of(device: &Device<Context>) -> Option<Self> {
+        Some(UsbLocation {
+            bus: device.bus_number(),
+            ports: device.port_numbers().ok()?,
+        })
+    }
+
+    fn matches(&self, device: &Device<Context>) -> bool {
+        let is_helios = device.device_descriptor()
+            .map(|descriptor| descriptor.vendor_id() == HELIOS_VID && descriptor.product_id() == HELIOS_PID)
+            .unwrap_or(false);
 
-            Ok((buffer, size))
-        }else {
-            Err(NativeHeliosError::DeviceNotOpened)
+        is_helios && UsbLocation::of(device).is_some_and(|location| location.bus == self.bus && location.ports == self.ports)
+    }
+}
+
+/// State of a dac at one point in time, see [NativeHeliosDac::snapshot]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DacSnapshot {
+    pub name: String,
+    pub firmware_version: FirmwareVersion,
+    pub status: DeviceStatus,
+}
+
+impl DacSnapshot {
+    /// Tells which fields changed from `self` to `other`
+    pub fn diff(&self, other: &DacSnapshot) -> DacDiff {
+        DacDiff {
+            name: self.name != other.name,
+            firmware_version: self.firmware_version != other.firmware_version,
+            status: self.status != other.status,
         }
     }
 }
 
+/// Fields which differ between two [DacSnapshot]s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DacDiff {
+    pub name: bool,
+    pub firmware_version: bool,
+    pub status: bool,
+}
+
+impl DacDiff {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == DacDiff::default()
+    }
+}
+
+/// Details identifying a dac, used by [NativeHeliosDac::reconnect] to notice a different dac
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceFingerprint {
+    pub name: String,
+    pub firmware_version: FirmwareVersion,
+}
+
+impl std::fmt::Display for DeviceFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} with firmware {}", self.name, self.firmware_version)
+    }
+}
+
+/// Settings of a dac which can be copied to another dac, see [NativeHeliosDac::config]
+///
+/// Only settings chosen by the user are included, not the connection or the limits reported by the dac.
+/// A running watchdog isn't included either, as it belongs to the connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DacConfig {
+    /// See [NativeHeliosDac::set_safety_limiter]
+    pub safety_limiter: Option<SafetyLimiter>,
+    /// See [NativeHeliosDac::set_calibration]
+    pub calibration: Option<Calibration>,
+    /// See [NativeHeliosDac::set_coordinate_space]
+    pub coordinate_space: Option<CoordinateSpace>,
+    /// See [NativeHeliosDac::set_timeout]
+    pub timeout: Option<Duration>,
+    /// See [NativeHeliosDac::with_auto_reconnect]
+    pub reconnect_retries: u8,
+    /// See [NativeHeliosDac::set_stop_on_drop]
+    pub stop_on_drop: bool,
+    /// See [NativeHeliosDac::set_accept_device_swap]
+    pub accept_device_swap: bool,
+}
+
+/// Details of a dac, see [NativeHeliosDac::device_info]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub firmware_version: FirmwareVersion,
+    /// Maximum number of points in a single frame
+    pub max_points: usize,
+    /// Maximum rate of output in points per second
+    pub max_pps: u32,
+    pub status: DeviceStatus,
+}
+
+impl std::fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let status = if self.status.ready() { "ready" } else { "not ready" };
+        write!(
+            f,
+            "{} (firmware {}, max {} points, max {} pps, {})",
+            self.name, self.firmware_version, self.max_points, self.max_pps, status
+        )
+    }
+}
+
+impl Drop for NativeHeliosDac {
+    fn drop(&mut self) {
+        if self.stop_on_drop && self.is_open() {
+            // errors can't be reported here, the dac might already be unplugged
+            let _ = self.stop();
+        }
+    }
+}
+
+pub(crate) struct EncodedFrame {
+    buffer: Vec<u8>,
+    safety_scale: Option<f32>,
+}
+
+/// Thread sending a stop command when it isn't fed in time
+struct Watchdog {
+    feed: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    fn start(transport: Arc<dyn Transport>, timeout: Duration) -> Self {
+        let (feed, food) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut stopped = false;
+            loop {
+                match food.recv_timeout(timeout) {
+                    Ok(()) => stopped = false,
+                    Err(RecvTimeoutError::Timeout) if !stopped => {
+                        let (buffer, size) = protocol::encode_command(&Command::Stop);
+                        let _ = transport.write_control(&buffer[0..size], CONTROL_TIMEOUT);
+                        stopped = true;
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        Watchdog {
+            feed: Some(feed),
+            thread: Some(thread),
+        }
+    }
+
+    fn feed(&self) {
+        if let Some(feed) = &self.feed {
+            let _ = feed.send(());
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        // disconnecting the channel ends the thread
+        self.feed = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn parse_status(response: &[u8]) -> Result<DeviceStatus> {
+    match expect_response(response, RESPONSE_GET_STATUS)? {
+        Response::Status(status) => Ok(status),
+        _ => Err(NativeHeliosError::InvalidDeviceResult),
+    }
+}
+
+/// Whether an error means the dac is gone and reconnecting might help
+fn connection_lost(err: &NativeHeliosError) -> bool {
+    matches!(err, NativeHeliosError::UsbError(rusb::Error::NoDevice | rusb::Error::Io | rusb::Error::Pipe))
+}
+
+/// Whether frames of this size are sent without their last point, the dac doesn't receive transfers of 45 + n * 64 points
+fn drops_last_point(points: usize) -> bool {
+    points >= 45 && (points - 45).is_multiple_of(64)
+}
+
+/// Decodes a response after making sure it answers the expected command
+fn expect_response(response: &[u8], expected: u8) -> Result<Response> {
+    match response {
+        [command, ..] if *command == expected => Ok(protocol::decode_response(response)?),
+        [command, ..] => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(expected, received = *command, "unexpected response");
+
+            Err(NativeHeliosError::UnexpectedResponse {
+                expected,
+                received: *command,
+            })
+        }
+        [] => Err(NativeHeliosError::InvalidDeviceResult),
+    }
+}
+
 impl From<rusb::Device<rusb::Context>> for NativeHeliosDac {
     fn from(device: Device<Context>) -> Self {
-        NativeHeliosDac::Idle(device)
+        let location = UsbLocation::of(&device);
+        let mut dac = NativeHeliosDac::new(DacState::Idle(device));
+        dac.location = location;
+
+        dac
     }
 }
 
 #[derive(Error, Debug)]
 pub enum NativeHeliosError {
+    #[error("no dac found")]
+    DeviceNotFound,
     #[error("device is not opened")]
     DeviceNotOpened,
+    #[error("invalid frame: {0}")]
+    InvalidFrame(#[from] FrameError),
+    #[error("safety limit exceeded: {0}")]
+    SafetyLimitExceeded(#[from] SafetyLimitExceeded),
+    #[error("invalid frame rate: {0}")]
+    InvalidFps(#[from] InvalidFps),
+    #[error("usb transfer timed out")]
+    Timeout,
     #[error("usb connection error: {0}")]
-    UsbError(#[from] rusb::Error),
+    UsbError(#[source] rusb::Error),
     #[error("usb device answered with invalid data")]
     InvalidDeviceResult,
+    #[error("usb device answered with response {received:#04x}, expected {expected:#04x}")]
+    UnexpectedResponse {
+        expected: u8,
+        received: u8,
+    },
+    #[error("expected {expected} but found {found} after reconnecting")]
+    DeviceMismatch {
+        expected: DeviceFingerprint,
+        found: DeviceFingerprint,
+    },
+    #[error("name is {0} bytes long, max is {}", MAX_NAME_LENGTH)]
+    NameTooLong(usize),
+    #[error("could not read file: {0}")]
+    IoError(#[from] std::io::Error),
     #[error("could not parse string: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
 }
+
+impl From<rusb::Error> for NativeHeliosError {
+    fn from(err: rusb::Error) -> Self {
+        match err {
+            rusb::Error::Timeout => NativeHeliosError::Timeout,
+            err => NativeHeliosError::UsbError(err),
+        }
+    }
+}
+
+impl From<ProtocolError> for NativeHeliosError {
+    fn from(_: ProtocolError) -> Self {
+        NativeHeliosError::InvalidDeviceResult
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, MockDac};
+
+    /// Points which differ from each other, so dropped or repeated points show up when comparing
+    fn numbered_points(count: usize) -> Vec<Point> {
+        (0..count)
+            .map(|i| Point {
+                coordinate: Coordinate { x: (i % 0x1000) as u16, y: (i / 0x1000) as u16 },
+                color: Color::new(255, 255, 255),
+                intensity: 255,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn writes_frames_of_max_points() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let points = numbered_points(Frame::MAX_POINTS);
+        dac.write_frame(Frame::new(30000, points.clone())).unwrap();
+
+        assert_eq!(mock.frames(), vec![Frame::new(30000, points)]);
+    }
+
+    #[test]
+    fn rejects_frames_above_max_points() {
+        let mut dac = NativeHeliosDac::from_transport(MockDac::new()).unwrap();
+        let result = dac.write_frame(Frame::new(30000, numbered_points(Frame::MAX_POINTS + 1)));
+
+        assert!(matches!(result, Err(NativeHeliosError::InvalidFrame(FrameError::TooManyPoints(_)))));
+    }
+
+    #[test]
+    fn usb_errors_have_a_source() {
+        let err = NativeHeliosError::from(rusb::Error::Io);
+
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn empty_frames_stop_the_output() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+        assert!(!mock.is_stopped());
+        dac.write_frame(Frame::new(30000, vec![])).unwrap();
+
+        assert!(mock.is_stopped());
+        assert_eq!(mock.frames().len(), 1);
+    }
+
+    #[test]
+    fn drops_last_point_of_frames_the_dac_cant_receive() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let points = numbered_points(45 + 64);
+        dac.write_frame(Frame::new(1090, points.clone())).unwrap();
+        dac.write_frame(Frame::new(1000, numbered_points(45 + 65))).unwrap();
+
+        let frames = mock.frames();
+        assert_eq!(frames[0], Frame::new(1080, points[..108].to_vec()));
+        assert_eq!(frames[1].points.len(), 45 + 65);
+        assert_eq!(frames[1].pps, 1000);
+    }
+
+    #[test]
+    fn ramp_ends_at_the_pps_of_the_frame() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let frame = Frame::new(30000, numbered_points(100));
+        let ramped = dac.write_frame_with_ramp(frame, Duration::from_millis(20), &AtomicBool::new(false));
+
+        assert!(ramped.unwrap());
+        let frames = mock.frames();
+        assert!(frames[0].pps < 30000);
+        assert_eq!(frames.last().unwrap().pps, 30000);
+    }
+
+    #[test]
+    fn cancelled_ramp_stops_the_output() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let frame = Frame::new(30000, numbered_points(100));
+        let ramped = dac.write_frame_with_ramp(frame, Duration::from_secs(60), &AtomicBool::new(true));
+
+        assert!(!ramped.unwrap());
+        assert!(mock.frames().is_empty());
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn waits_until_single_frames_are_drawn() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let start = Instant::now();
+        dac.write_frame(Frame::new_with_flags(1000, numbered_points(50), WriteFrameFlags::SINGLE_MODE)).unwrap();
+
+        assert!(dac.wait_until_drawn().unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn ready_times_out_when_the_dac_is_busy() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        assert!(dac.wait_until_ready(Duration::from_millis(5)).unwrap());
+
+        mock.set_ready(false);
+        assert!(!dac.wait_until_ready(Duration::from_millis(5)).unwrap());
+        assert!(!dac.wait_until_drawn().unwrap());
+    }
+
+    #[test]
+    fn shutdown_blanks_and_stops_the_output() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+        dac.shutdown().unwrap();
+
+        let frames = mock.frames();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[1].points.iter().all(Point::is_blanked));
+        assert!(frames[1].flags.contains(WriteFrameFlags::SINGLE_MODE));
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn shutdown_of_a_stopped_dac() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        assert!(mock.is_stopped());
+        dac.shutdown().unwrap();
+
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn shutdown_fails_when_the_dac_stays_busy() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.set_ready(false);
+
+        assert!(matches!(dac.shutdown(), Err(NativeHeliosError::Timeout)));
+    }
+
+    #[test]
+    fn resends_frames_after_reconnecting() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap().with_auto_reconnect(2);
+        mock.fail_frame_writes(1);
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+
+        assert_eq!(mock.frames().len(), 1);
+    }
+
+    #[test]
+    fn stops_retrying_when_another_dac_was_plugged_in() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap().with_auto_reconnect(3);
+        mock.set_name("Other");
+        mock.fail_frame_writes(1);
+        let result = dac.write_frame(Frame::new(30000, numbered_points(10)));
+
+        assert!(matches!(result, Err(NativeHeliosError::DeviceMismatch { .. })));
+        assert!(mock.frames().is_empty());
+    }
+
+    #[test]
+    fn paced_frames_are_split_without_gaps() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let points = numbered_points(Frame::MAX_POINTS * 2 + 123);
+        dac.write_frame_paced(Frame::new(30000, points.clone())).unwrap();
+
+        let frames = mock.frames();
+        assert!(frames.len() > 1);
+        for frame in &frames {
+            assert_eq!(frame.pps, 30000);
+            assert!(frame.flags.contains(WriteFrameFlags::SINGLE_MODE));
+        }
+        let received: Vec<Point> = frames.into_iter().flat_map(|frame| frame.points).collect();
+        assert_eq!(received, points);
+    }
+
+    #[test]
+    fn paced_frames_within_the_limit_are_drawn_once() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.write_frame_paced(Frame::new(30000, numbered_points(100))).unwrap();
+
+        assert_eq!(mock.frames()[0].flags, WriteFrameFlags::SINGLE_MODE);
+    }
+
+    #[test]
+    fn streaming_to_a_dac_which_is_never_ready_times_out() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.set_ready(false);
+        let result = dac.stream_points(numbered_points(1000), 30000);
+
+        assert!(matches!(result, Err(NativeHeliosError::Timeout)));
+        assert!(mock.frames().is_empty());
+    }
+
+    #[test]
+    fn streamed_chunks_avoid_sizes_the_dac_cant_receive() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let points = numbered_points(45 + 64 * 10);
+        // 20ms at this pps are 45 + 64 points
+        dac.stream_points(points.clone(), 5440).unwrap();
+
+        let frames = mock.frames();
+        assert!(frames.iter().all(|frame| !drops_last_point(frame.points.len())));
+        let received: Vec<Point> = frames.into_iter().flat_map(|frame| frame.points).collect();
+        assert_eq!(received, points);
+    }
+
+
+    #[test]
+    fn reconnect_rejects_another_dac() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.set_name("Other");
+
+        match dac.reconnect() {
+            Err(NativeHeliosError::DeviceMismatch { expected, found }) => {
+                assert_eq!(expected.name, "Mock");
+                assert_eq!(found.name, "Other");
+                assert_eq!(expected.firmware_version, found.firmware_version);
+            }
+            result => panic!("expected a device mismatch, got {:?}", result),
+        }
+        assert_eq!(dac.fingerprint().unwrap().name, "Mock");
+        assert!(mock.frames().is_empty());
+    }
+
+    #[test]
+    fn reconnect_rejects_another_firmware_version() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.set_firmware_version(7);
+
+        assert!(matches!(dac.reconnect(), Err(NativeHeliosError::DeviceMismatch { .. })));
+    }
+
+    #[test]
+    fn reconnect_accepts_another_dac_when_swaps_are_accepted() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.set_accept_device_swap(true);
+        mock.set_name("Other");
+
+        dac.reconnect().unwrap();
+        assert_eq!(dac.fingerprint().unwrap().name, "Other");
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+        assert_eq!(mock.frames().len(), 1);
+    }
+
+    #[test]
+    fn reconnect_accepts_the_same_dac() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        dac.reconnect().unwrap();
+        assert_eq!(dac.fingerprint().unwrap().name, "Mock");
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+        assert_eq!(mock.frames().len(), 1);
+    }
+
+
+    #[test]
+    fn watchdog_stops_the_output_when_not_fed() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.start_watchdog(Duration::from_millis(20)).unwrap();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+        assert!(!mock.is_stopped());
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn watchdog_is_reset_by_feeding_and_commands() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.start_watchdog(Duration::from_millis(60)).unwrap();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+
+        for _ in 0..8 {
+            thread::sleep(Duration::from_millis(10));
+            dac.feed_watchdog();
+        }
+        for _ in 0..8 {
+            thread::sleep(Duration::from_millis(10));
+            dac.status().unwrap();
+        }
+        assert!(!mock.is_stopped());
+    }
+
+    #[test]
+    fn stopped_watchdog_leaves_the_output_running() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.start_watchdog(Duration::from_millis(20)).unwrap();
+        dac.stop_watchdog();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+
+        thread::sleep(Duration::from_millis(60));
+        assert!(!mock.is_stopped());
+    }
+
+    #[test]
+    fn watchdog_is_restarted_after_reconnecting() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        dac.start_watchdog(Duration::from_millis(20)).unwrap();
+        dac.reconnect().unwrap();
+        dac.write_frame(Frame::new(30000, numbered_points(10))).unwrap();
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(mock.is_stopped());
+    }
+
+
+    #[test]
+    fn try_status_returns_none_until_the_answer_arrived() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.hold_responses(true);
+
+        assert_eq!(dac.try_status().unwrap(), None);
+        assert_eq!(dac.try_status().unwrap(), None);
+        mock.hold_responses(false);
+        assert_eq!(dac.try_status().unwrap(), Some(DeviceStatus::Ready));
+        // the status was only requested once
+        assert_eq!(dac.flush().unwrap(), 0);
+    }
+
+    #[test]
+    fn try_status_requests_a_new_status_after_an_answer() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        assert_eq!(dac.try_status().unwrap(), Some(DeviceStatus::Ready));
+        mock.set_ready(false);
+        assert_eq!(dac.try_status().unwrap(), Some(DeviceStatus::NotReady));
+    }
+
+    #[test]
+    fn status_takes_the_answer_to_a_pending_try_status() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.hold_responses(true);
+        assert_eq!(dac.try_status().unwrap(), None);
+
+        mock.hold_responses(false);
+        assert_eq!(dac.status().unwrap(), DeviceStatus::Ready);
+        assert_eq!(dac.flush().unwrap(), 0);
+    }
+
+    #[test]
+    fn commands_discard_the_answer_to_a_pending_try_status() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        mock.hold_responses(true);
+        assert_eq!(dac.try_status().unwrap(), None);
+
+        mock.hold_responses(false);
+        assert_eq!(dac.name().unwrap(), "Mock");
+        assert_eq!(dac.flush().unwrap(), 0);
+    }
+
+
+    #[test]
+    fn snapshots_read_the_state_of_the_dac() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+
+        let snapshot = dac.snapshot().unwrap();
+        assert_eq!(snapshot.name, "Mock");
+        assert_eq!(snapshot.firmware_version, FirmwareVersion::new(6));
+        assert_eq!(snapshot.status, DeviceStatus::Ready);
+        assert!(snapshot.diff(&dac.snapshot().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn snapshot_diffs_report_each_changed_field() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let before = dac.snapshot().unwrap();
+
+        mock.set_ready(false);
+        let after = dac.snapshot().unwrap();
+        assert_eq!(before.diff(&after), DacDiff { status: true, ..DacDiff::default() });
+        assert!(!before.diff(&after).is_empty());
+
+        mock.set_name("Other");
+        mock.set_firmware_version(7);
+        let diff = after.diff(&dac.snapshot().unwrap());
+        assert_eq!(diff, DacDiff { name: true, firmware_version: true, status: false });
+    }
+
+
+    #[test]
+    fn shows_are_validated_against_the_safety_limiter_of_the_dac() {
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock).unwrap();
+        let frames = vec![numbered_points(100)];
+        assert!(dac.validate_show(&frames, 30.0).is_empty());
+
+        dac.set_safety_limiter(Some(SafetyLimiter::new(1.0).with_max_point_power(0.5)));
+        let issues = dac.validate_show(&frames, 30.0);
+        assert!(matches!(issues[..], [ValidationIssue::SafetyLimitExceeded { frame: 0, point: 0, .. }]));
+    }
+}
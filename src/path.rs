@@ -0,0 +1,1009 @@
+//! Processing passes over the points of a frame
+
+use std::iter;
+
+use crate::frame::{Color, Coordinate, Frame, Point};
+
+/// Inserts blanked points wherever consecutive points are further apart than `threshold`
+///
+/// Blanked points are added at the source and the destination of each jump to let the scanner settle.
+/// Their amount grows with the length of the jump.
+pub fn insert_blanking(points: &mut Vec<Point>, threshold: u16) {
+    let threshold = f32::from(threshold.max(1));
+    let mut result = Vec::with_capacity(points.len());
+    let mut previous: Option<Point> = None;
+    for point in points.drain(..) {
+        if let Some(previous) = previous {
+            let distance = distance(&previous, &point);
+            if distance > threshold {
+                let count = (distance / threshold).ceil() as usize;
+                result.extend(iter::repeat_n(previous.blanked(), count));
+                result.extend(iter::repeat_n(point.blanked(), count));
+            }
+        }
+        result.push(point);
+        previous = Some(point);
+    }
+    *points = result;
+}
+
+/// Clamps all points to the coordinate space, returns the amount of points which were out of bounds
+///
+/// See [Point::clamp_to_bounds].
+pub fn clamp_all(points: &mut [Point]) -> usize {
+    points.iter_mut().map(Point::clamp_to_bounds).filter(|clamped| *clamped).count()
+}
+
+/// Makes sure `count` blanked points at the position of the first lit point come right before it
+///
+/// This lets the scanner arrive before the laser turns on. Blanked points already at that position count
+/// towards `count`, so applying it again doesn't add more points. Returns the amount of inserted points.
+pub fn add_lead_in(points: &mut Vec<Point>, count: u8) -> usize {
+    let first = match points.iter().position(|point| !point.is_blanked()) {
+        Some(first) => first,
+        None => return 0,
+    };
+    let coordinate = points[first].coordinate;
+    let existing = points[..first]
+        .iter()
+        .rev()
+        .take_while(|point| point.coordinate == coordinate)
+        .count();
+    let missing = usize::from(count).saturating_sub(existing);
+    points.splice(first..first, iter::repeat_n(points[first].blanked(), missing));
+
+    missing
+}
+
+/// Makes sure `count` blanked points at the position of the last lit point follow it, see [add_lead_in]
+pub fn add_lead_out(points: &mut Vec<Point>, count: u8) -> usize {
+    let last = match points.iter().rposition(|point| !point.is_blanked()) {
+        Some(last) => last,
+        None => return 0,
+    };
+    let coordinate = points[last].coordinate;
+    let existing = points[last + 1..]
+        .iter()
+        .take_while(|point| point.coordinate == coordinate)
+        .count();
+    let missing = usize::from(count).saturating_sub(existing);
+    points.splice(last + 1..last + 1, iter::repeat_n(points[last].blanked(), missing));
+
+    missing
+}
+
+/// Collapses runs of consecutive points within `coord_tol` of each other and with the same color into one point
+///
+/// Runs of at least `min_dwell` points are treated as intentional dwell points and kept as they are,
+/// a `min_dwell` of 0 collapses all runs. Returns the amount of removed points.
+pub fn dedup_consecutive(points: &mut Vec<Point>, coord_tol: i16, min_dwell: usize) -> usize {
+    let length = points.len();
+    let mut result = Vec::with_capacity(length);
+    let mut start = 0;
+    while start < length {
+        let mut end = start + 1;
+        while end < length && points[start].approx_eq(&points[end], coord_tol, 0) {
+            end += 1;
+        }
+        if min_dwell > 0 && end - start >= min_dwell {
+            result.extend_from_slice(&points[start..end]);
+        } else {
+            result.push(points[start]);
+        }
+        start = end;
+    }
+    *points = result;
+
+    length - points.len()
+}
+
+/// Repeats corner points `dwell` times where the path turns by more than `angle_threshold` radians
+///
+/// Straight runs are left untouched. Returns the amount of inserted points.
+pub fn add_dwell_points(points: &mut Vec<Point>, angle_threshold: f32, dwell: u8) -> usize {
+    if points.len() < 3 || dwell == 0 {
+        return 0;
+    }
+    let mut result = Vec::with_capacity(points.len());
+    let mut inserted = 0;
+    result.push(points[0]);
+    for window in points.windows(3) {
+        let (previous, corner, next) = (&window[0], &window[1], &window[2]);
+        result.push(*corner);
+        if turn_angle(previous, corner, next).is_some_and(|angle| angle > angle_threshold) {
+            result.extend(iter::repeat_n(*corner, usize::from(dwell)));
+            inserted += usize::from(dwell);
+        }
+    }
+    result.push(points[points.len() - 1]);
+    *points = result;
+
+    inserted
+}
+
+/// Corners turning by more than this many radians are counted as sharp by [analyze]
+pub const SHARP_CORNER_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+
+/// Collects statistics about how well the points will scan without modifying them
+pub fn analyze(points: &[Point]) -> FrameStats {
+    let mut stats = FrameStats {
+        points: points.len(),
+        ..FrameStats::default()
+    };
+    if points.is_empty() {
+        return stats;
+    }
+    let blanked = points.iter().filter(|point| point.is_blanked()).count();
+    stats.blanked_ratio = blanked as f32 / points.len() as f32;
+    for window in points.windows(2) {
+        stats.max_segment_length = stats.max_segment_length.max(distance(&window[0], &window[1]));
+    }
+    let mut acceleration = 0.0;
+    for window in points.windows(3) {
+        let (previous, corner, next) = (&window[0], &window[1], &window[2]);
+        if turn_angle(previous, corner, next).is_some_and(|angle| angle > SHARP_CORNER_ANGLE) {
+            stats.sharp_corners += 1;
+        }
+        let (ax, ay) = delta(previous, corner);
+        let (bx, by) = delta(corner, next);
+        acceleration += (bx - ax).hypot(by - ay);
+    }
+    stats.galvo_stress = acceleration / points.len() as f32;
+
+    stats
+}
+
+/// Result of [analyze]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    /// Amount of analyzed points
+    pub points: usize,
+    /// Longest distance between two consecutive points in coordinate units
+    pub max_segment_length: f32,
+    /// Amount of corners turning by more than [SHARP_CORNER_ANGLE]
+    pub sharp_corners: usize,
+    /// Share of blanked points from 0.0 to 1.0
+    pub blanked_ratio: f32,
+    /// Average change of velocity per point in coordinate units
+    ///
+    /// Higher values mean the mirrors have to accelerate harder, smooth paths stay close to 0.
+    pub galvo_stress: f32,
+}
+
+impl std::fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} points, max segment {:.1}, {} sharp corners, {:.1}% blanked, stress {:.1}",
+            self.points,
+            self.max_segment_length,
+            self.sharp_corners,
+            self.blanked_ratio * 100.0,
+            self.galvo_stress
+        )
+    }
+}
+
+/// Reduces the amount of points with the Ramer-Douglas-Peucker algorithm
+///
+/// Points whose distance to the simplified path is at most `epsilon` coordinate units are dropped.
+/// Only runs of lit points sharing the same color are simplified, so color changes stay in place
+/// and blanked points are always kept.
+pub fn simplify(points: &[Point], epsilon: f32) -> Vec<Point> {
+    let mut keep = vec![false; points.len()];
+    let mut start = 0;
+    while start < points.len() {
+        let mut end = start + 1;
+        while end < points.len() && same_color(&points[start], &points[end]) {
+            end += 1;
+        }
+        if points[start].is_blanked() {
+            keep[start..end].iter_mut().for_each(|keep| *keep = true);
+        } else {
+            douglas_peucker(&points[start..end], epsilon, &mut keep[start..end]);
+        }
+        start = end;
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(point, _)| *point)
+        .collect()
+}
+
+/// Maximum distance in coordinate units between the first and the last point of a closed path
+pub const CLOSED_TOLERANCE: f32 = 2.0;
+
+/// Same as [simplify] but keeps paths closed whose first and last points are lit and within [CLOSED_TOLERANCE]
+///
+/// A closed path is treated as a cycle starting at the point furthest from its first point,
+/// so the join between the last and the first point is simplified like any other point.
+/// The result starts and ends at that point. Open paths are passed to [simplify] as they are.
+pub fn simplify_closed(points: &[Point], epsilon: f32) -> Vec<Point> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if points.len() > 3 => (first, last),
+        _ => return simplify(points, epsilon),
+    };
+    if first.is_blanked() || last.is_blanked() || distance(first, last) > CLOSED_TOLERANCE {
+        return simplify(points, epsilon);
+    }
+    // the last point closes the cycle, the point furthest from the old join is kept by any simplification
+    let cycle = &points[..points.len() - 1];
+    let start = (0..cycle.len())
+        .max_by(|a, b| distance(first, &cycle[*a]).total_cmp(&distance(first, &cycle[*b])))
+        .unwrap_or(0);
+    let mut rotated = Vec::with_capacity(points.len());
+    rotated.extend_from_slice(&cycle[start..]);
+    rotated.extend_from_slice(&cycle[..=start]);
+
+    simplify(&rotated, epsilon)
+}
+
+/// Marks the points to keep, always keeping the first and last point
+fn douglas_peucker(points: &[Point], epsilon: f32, keep: &mut [bool]) {
+    let last = points.len() - 1;
+    keep[0] = true;
+    keep[last] = true;
+    let mut segments = vec![(0, last)];
+    while let Some((start, end)) = segments.pop() {
+        let mut max_distance = 0.0;
+        let mut furthest = start;
+        for index in start + 1..end {
+            let distance = segment_distance(&points[index], &points[start], &points[end]);
+            if distance > max_distance {
+                max_distance = distance;
+                furthest = index;
+            }
+        }
+        if max_distance > epsilon {
+            keep[furthest] = true;
+            segments.push((start, furthest));
+            segments.push((furthest, end));
+        }
+    }
+}
+
+/// Distance of `point` to the segment from `start` to `end`
+fn segment_distance(point: &Point, start: &Point, end: &Point) -> f32 {
+    let (sx, sy) = delta(start, end);
+    let (px, py) = delta(start, point);
+    let length = sx * sx + sy * sy;
+    if length == 0.0 {
+        return px.hypot(py);
+    }
+    let t = ((px * sx + py * sy) / length).clamp(0.0, 1.0);
+
+    (px - t * sx).hypot(py - t * sy)
+}
+
+fn same_color(a: &Point, b: &Point) -> bool {
+    a.color == b.color && a.intensity == b.intensity
+}
+
+/// How often [optimize] doubles the simplification epsilon at most to get below the budget
+const MAX_EPSILON_DOUBLINGS: u32 = 12;
+
+/// Settings of [optimize]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizeConfig {
+    /// Maximum amount of points of the result
+    pub budget: usize,
+    /// Initial epsilon of the simplification, see [simplify]
+    pub epsilon: f32,
+    /// Repetitions of sharp corners, see [add_dwell_points]
+    pub dwell: u8,
+    /// Distance above which jumps are blanked, see [insert_blanking]
+    pub blank_threshold: u16,
+}
+
+/// Fits into a single frame and uses the defaults of the individual passes
+impl Default for OptimizeConfig {
+    fn default() -> Self {
+        OptimizeConfig {
+            budget: Frame::MAX_POINTS,
+            epsilon: 1.0,
+            dwell: 2,
+            blank_threshold: 256,
+        }
+    }
+}
+
+/// Result of [optimize] with the work done by each pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimized {
+    pub points: Vec<Point>,
+    /// Amount of duplicate points removed
+    pub deduplicated: usize,
+    /// Amount of points removed by the simplification
+    pub simplified: usize,
+    /// Amount of dwell points inserted at sharp corners
+    pub dwell_points: usize,
+    /// Amount of blanked points inserted at jumps
+    pub blanking_points: usize,
+    /// Epsilon of the final simplification, higher than the configured one when it had to be raised
+    pub epsilon: f32,
+}
+
+/// Prepares raw points for scanning by removing duplicates, simplifying, adding dwell points at sharp corners
+/// and blanking jumps, in that order
+///
+/// When the result has more points than `config.budget`, the simplification is repeated with a doubled epsilon
+/// until it fits. The result can still exceed the budget when the blanked and dwell points alone don't fit.
+pub fn optimize(points: &[Point], config: OptimizeConfig) -> Optimized {
+    let mut deduplicated = points.to_vec();
+    let removed = dedup_consecutive(&mut deduplicated, 0, 0);
+    let mut epsilon = config.epsilon.max(0.0);
+    let mut doublings = 0;
+    loop {
+        let mut result = simplify(&deduplicated, epsilon);
+        let simplified = deduplicated.len() - result.len();
+        let dwell_points = add_dwell_points(&mut result, SHARP_CORNER_ANGLE, config.dwell);
+        let before_blanking = result.len();
+        insert_blanking(&mut result, config.blank_threshold);
+        if result.len() <= config.budget || doublings == MAX_EPSILON_DOUBLINGS {
+            return Optimized {
+                blanking_points: result.len() - before_blanking,
+                points: result,
+                deduplicated: removed,
+                simplified,
+                dwell_points,
+                epsilon,
+            };
+        }
+        epsilon = (epsilon * 2.0).max(1.0);
+        doublings += 1;
+    }
+}
+
+/// Inserts points along a Catmull-Rom spline through the points
+///
+/// Each segment between two lit points is split into `samples_per_segment` parts, colors are interpolated linearly.
+/// Blanked points are kept as they are and break the spline.
+/// The amount of samples is reduced to stay below `max_points`, but the input points are always kept.
+pub fn smooth_catmull_rom(points: &[Point], samples_per_segment: u8, max_points: usize) -> Vec<Point> {
+    let is_lit_segment = |index: usize| !points[index].is_blanked() && !points[index + 1].is_blanked();
+    let segments = (0..points.len().saturating_sub(1)).filter(|index| is_lit_segment(*index)).count();
+    let mut samples = usize::from(samples_per_segment.max(1));
+    if let Some(budget) = max_points.saturating_sub(points.len()).checked_div(segments) {
+        samples = samples.min(budget + 1);
+    }
+
+    let mut result = Vec::with_capacity(points.len() + segments * (samples - 1));
+    for (index, point) in points.iter().enumerate() {
+        result.push(*point);
+        if index + 1 == points.len() || !is_lit_segment(index) {
+            continue;
+        }
+        let (start, end) = (point, &points[index + 1]);
+        // mirror the ends of the spline onto the segment itself
+        let before = index
+            .checked_sub(1)
+            .map(|index| &points[index])
+            .filter(|point| !point.is_blanked())
+            .unwrap_or(start);
+        let after = points.get(index + 2).filter(|point| !point.is_blanked()).unwrap_or(end);
+        for sample in 1..samples {
+            let t = sample as f32 / samples as f32;
+            let mut point = interpolate_point(start, end, t, 1.0);
+            point.coordinate = Coordinate {
+                x: catmull_rom(before.coordinate.x, start.coordinate.x, end.coordinate.x, after.coordinate.x, t),
+                y: catmull_rom(before.coordinate.y, start.coordinate.y, end.coordinate.y, after.coordinate.y, t),
+            };
+            result.push(point);
+        }
+    }
+
+    result
+}
+
+fn catmull_rom(p0: u16, p1: u16, p2: u16, p3: u16, t: f32) -> u16 {
+    let (p0, p1, p2, p3) = (f32::from(p0), f32::from(p1), f32::from(p2), f32::from(p3));
+    let value = 0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+
+    value.round().clamp(0.0, f32::from(Coordinate::MAX)) as u16
+}
+
+/// Redistributes `target` points evenly along the path, keeping its first and last point
+///
+/// Works for both more and fewer points than the path has. Colors are interpolated between neighbours,
+/// points on a segment leading to or from a blanked point are blanked, so blanked parts stay where they were.
+/// Paths whose points are all at the same position are resampled by index instead.
+pub fn resample(points: &[Point], target: usize) -> Vec<Point> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if target > 1 && points.len() > 1 => (*first, *last),
+        (Some(first), _) => return vec![*first; target],
+        (None, _) => return Vec::new(),
+    };
+    let mut lengths = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    lengths.push(total);
+    for segment in points.windows(2) {
+        total += distance(&segment[0], &segment[1]);
+        lengths.push(total);
+    }
+    if total == 0.0 {
+        lengths = (0..points.len()).map(|index| index as f32).collect();
+        total = (points.len() - 1) as f32;
+    }
+
+    let mut result = Vec::with_capacity(target);
+    let mut segment = 0;
+    for sample in 0..target - 1 {
+        let position = total * sample as f32 / (target - 1) as f32;
+        while segment < points.len() - 2 && lengths[segment + 1] < position {
+            segment += 1;
+        }
+        let (a, b) = (&points[segment], &points[segment + 1]);
+        let length = lengths[segment + 1] - lengths[segment];
+        let t = if length > 0.0 { (position - lengths[segment]) / length } else { 0.0 };
+        let mut point = interpolate_point(a, b, t.clamp(0.0, 1.0), 1.0);
+        if t > 0.0 && (a.is_blanked() || b.is_blanked()) {
+            point.blank();
+        }
+        result.push(point);
+    }
+    result[0] = first;
+    result.push(last);
+
+    result
+}
+
+/// Linearly interpolates position, color and intensity of each point from `a` (t = 0) to `b` (t = 1)
+pub fn interpolate(a: &[Point], b: &[Point], t: f32) -> Result<Vec<Point>, LengthMismatch> {
+    interpolate_with_gamma(a, b, t, 1.0)
+}
+
+/// Same as [interpolate] but blends colors in linear light, decoding them with `gamma` first
+///
+/// Blending gamma encoded colors directly makes the midpoint of two colors look too dark.
+/// A `gamma` which isn't a finite positive number is treated as 1.0, blending the colors as they are.
+pub fn interpolate_with_gamma(a: &[Point], b: &[Point], t: f32, gamma: f32) -> Result<Vec<Point>, LengthMismatch> {
+    if a.len() != b.len() {
+        return Err(LengthMismatch { a: a.len(), b: b.len() });
+    }
+    let t = t.clamp(0.0, 1.0);
+    let gamma = if gamma.is_finite() && gamma > 0.0 { gamma } else { 1.0 };
+    let points = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| interpolate_point(a, b, t, gamma))
+        .collect();
+
+    Ok(points)
+}
+
+/// Morphs from `a` to `b` in `steps` frames, the first frame equals `a` and the last equals `b`
+pub fn interpolate_sequence(a: &[Point], b: &[Point], steps: usize) -> Result<Vec<Vec<Point>>, LengthMismatch> {
+    interpolate_sequence_with_gamma(a, b, steps, 1.0)
+}
+
+/// Same as [interpolate_sequence] but blends colors in linear light, see [interpolate_with_gamma]
+pub fn interpolate_sequence_with_gamma(
+    a: &[Point],
+    b: &[Point],
+    steps: usize,
+    gamma: f32,
+) -> Result<Vec<Vec<Point>>, LengthMismatch> {
+    (0..steps)
+        .map(|step| {
+            let t = if steps > 1 { step as f32 / (steps - 1) as f32 } else { 0.0 };
+            interpolate_with_gamma(a, b, t, gamma)
+        })
+        .collect()
+}
+
+/// Blends the colors of `b` into the points of `a`, keeping the positions of `a`, see [Point::blend]
+pub fn blend_frames(a: &[Point], b: &[Point], alpha: f32) -> Result<Vec<Point>, LengthMismatch> {
+    if a.len() != b.len() {
+        return Err(LengthMismatch { a: a.len(), b: b.len() });
+    }
+
+    Ok(a.iter().zip(b).map(|(a, b)| a.blend(b, alpha)).collect())
+}
+
+/// Two paths which need the same amount of points have different lengths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub a: usize,
+    pub b: usize,
+}
+
+impl std::fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "paths have different lengths of {} and {} points", self.a, self.b)
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
+
+fn interpolate_point(a: &Point, b: &Point, t: f32, gamma: f32) -> Point {
+    Point {
+        coordinate: Coordinate {
+            x: lerp(f32::from(a.coordinate.x), f32::from(b.coordinate.x), t).round() as u16,
+            y: lerp(f32::from(a.coordinate.y), f32::from(b.coordinate.y), t).round() as u16,
+        },
+        color: Color::new(
+            lerp_channel(a.color.r, b.color.r, t, gamma),
+            lerp_channel(a.color.g, b.color.g, t, gamma),
+            lerp_channel(a.color.b, b.color.b, t, gamma),
+        ),
+        intensity: lerp(f32::from(a.intensity), f32::from(b.intensity), t).round() as u8,
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_channel(a: u8, b: u8, t: f32, gamma: f32) -> u8 {
+    let a = (f32::from(a) / 255.0).powf(gamma);
+    let b = (f32::from(b) / 255.0).powf(gamma);
+
+    (lerp(a, b, t).powf(1.0 / gamma) * 255.0).round() as u8
+}
+
+/// Angle between the segments meeting at `corner`, zero for a straight line
+///
+/// Returns `None` when one of the segments has no length.
+fn turn_angle(previous: &Point, corner: &Point, next: &Point) -> Option<f32> {
+    let (ax, ay) = delta(previous, corner);
+    let (bx, by) = delta(corner, next);
+    let lengths = ax.hypot(ay) * bx.hypot(by);
+    if lengths == 0.0 {
+        return None;
+    }
+    let cos = ((ax * bx + ay * by) / lengths).clamp(-1.0, 1.0);
+
+    Some(cos.acos())
+}
+
+fn delta(from: &Point, to: &Point) -> (f32, f32) {
+    (
+        f32::from(to.coordinate.x) - f32::from(from.coordinate.x),
+        f32::from(to.coordinate.y) - f32::from(from.coordinate.y),
+    )
+}
+
+/// Euclidean distance between two points in coordinate units
+fn distance(a: &Point, b: &Point) -> f32 {
+    let (dx, dy) = delta(a, b);
+
+    dx.hypot(dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(x: u16, y: u16) -> Point {
+        Point::builder().position(x, y).rgb(255, 255, 255).build()
+    }
+
+    fn dark(x: u16, y: u16) -> Point {
+        lit(x, y).blanked()
+    }
+
+    #[test]
+    fn blanks_jumps_at_both_ends() {
+        let mut points = vec![lit(0, 0), lit(1000, 0)];
+        insert_blanking(&mut points, 256);
+
+        let mut expected = vec![lit(0, 0)];
+        expected.extend(iter::repeat_n(dark(0, 0), 4));
+        expected.extend(iter::repeat_n(dark(1000, 0), 4));
+        expected.push(lit(1000, 0));
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn keeps_points_closer_than_the_threshold() {
+        let original = vec![lit(0, 0), lit(100, 100), lit(200, 0), dark(300, 0)];
+        let mut points = original.clone();
+        insert_blanking(&mut points, 256);
+
+        assert_eq!(points, original);
+    }
+
+    #[test]
+    fn dwells_on_sharp_corners_only() {
+        let mut points = vec![lit(0, 0), lit(100, 0), lit(200, 0), lit(200, 100), lit(0, 0)];
+        let inserted = add_dwell_points(&mut points, SHARP_CORNER_ANGLE * 0.9, 2);
+
+        // the right angle at (200, 0) and the sharp turn at (200, 100) dwell, the straight run doesn't
+        assert_eq!(inserted, 4);
+        assert_eq!(
+            points,
+            vec![
+                lit(0, 0),
+                lit(100, 0),
+                lit(200, 0),
+                lit(200, 0),
+                lit(200, 0),
+                lit(200, 100),
+                lit(200, 100),
+                lit(200, 100),
+                lit(0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_paths_get_no_dwell_points() {
+        let mut points = vec![lit(0, 0), lit(100, 0)];
+
+        assert_eq!(add_dwell_points(&mut points, 0.0, 5), 0);
+        assert_eq!(points.len(), 2);
+    }
+
+    #[test]
+    fn simplify_drops_points_on_straight_lines() {
+        let points: Vec<Point> = (0..=10).map(|i| lit(i * 100, 0)).collect();
+
+        assert_eq!(simplify(&points, 1.0), vec![lit(0, 0), lit(1000, 0)]);
+    }
+
+    #[test]
+    fn simplify_keeps_corners_and_endpoints() {
+        let points = vec![lit(0, 0), lit(50, 1), lit(100, 0), lit(100, 50), lit(100, 100)];
+
+        assert_eq!(simplify(&points, 2.0), vec![lit(0, 0), lit(100, 0), lit(100, 100)]);
+        assert_eq!(simplify(&points, 0.5), vec![lit(0, 0), lit(50, 1), lit(100, 0), lit(100, 100)]);
+    }
+
+    #[test]
+    fn simplify_keeps_blanked_runs_and_color_changes() {
+        let red = Point::builder().position(300, 0).rgb(255, 0, 0).build();
+        let points = vec![
+            lit(0, 0),
+            lit(100, 0),
+            lit(200, 0),
+            dark(200, 0),
+            dark(250, 0),
+            dark(300, 0),
+            red,
+            lit(400, 0),
+            lit(500, 0),
+        ];
+
+        assert_eq!(
+            simplify(&points, 1.0),
+            vec![lit(0, 0), lit(200, 0), dark(200, 0), dark(250, 0), dark(300, 0), red, lit(400, 0), lit(500, 0)]
+        );
+    }
+
+    #[test]
+    fn simplify_handles_short_paths() {
+        assert_eq!(simplify(&[], 1.0), vec![]);
+        assert_eq!(simplify(&[lit(1, 1)], 1.0), vec![lit(1, 1)]);
+    }
+
+    #[test]
+    fn interpolates_positions_and_colors() {
+        let a = [Point::builder().position(0, 100).rgb(0, 100, 200).intensity(0).build()];
+        let b = [Point::builder().position(100, 0).rgb(200, 100, 0).intensity(200).build()];
+
+        assert_eq!(interpolate(&a, &b, 0.0), Ok(a.to_vec()));
+        assert_eq!(interpolate(&a, &b, 1.0), Ok(b.to_vec()));
+        assert_eq!(
+            interpolate(&a, &b, 0.25),
+            Ok(vec![Point::builder().position(25, 75).rgb(50, 100, 150).intensity(50).build()])
+        );
+        // t outside of 0 to 1 is clamped
+        assert_eq!(interpolate(&a, &b, 2.0), Ok(b.to_vec()));
+    }
+
+    #[test]
+    fn interpolation_requires_equal_lengths() {
+        assert_eq!(interpolate(&[lit(0, 0)], &[], 0.5), Err(LengthMismatch { a: 1, b: 0 }));
+        assert_eq!(interpolate_sequence(&[], &[lit(0, 0)], 3), Err(LengthMismatch { a: 0, b: 1 }));
+    }
+
+    #[test]
+    fn interpolates_colors_in_linear_light() {
+        let a = [Point::builder().rgb(0, 0, 0).build()];
+        let b = [Point::builder().rgb(255, 255, 255).build()];
+
+        assert_eq!(interpolate(&a, &b, 0.5).unwrap()[0].color, Color::new(128, 128, 128));
+        assert_eq!(interpolate_with_gamma(&a, &b, 0.5, 2.2).unwrap()[0].color, Color::new(186, 186, 186));
+    }
+
+    #[test]
+    fn sequences_start_at_a_and_end_at_b() {
+        let a = [lit(0, 0), lit(100, 100)];
+        let b = [lit(100, 0), lit(0, 100)];
+        let frames = interpolate_sequence(&a, &b, 5).unwrap();
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], a.to_vec());
+        assert_eq!(frames[2], vec![lit(50, 0), lit(50, 100)]);
+        assert_eq!(frames[4], b.to_vec());
+        assert_eq!(interpolate_sequence(&a, &b, 1).unwrap(), vec![a.to_vec()]);
+    }
+
+    #[test]
+    fn invalid_gammas_blend_colors_as_they_are() {
+        let a = [Point::builder().rgb(0, 0, 0).build()];
+        let b = [Point::builder().rgb(255, 255, 255).build()];
+        let linear = interpolate(&a, &b, 0.5).unwrap();
+
+        for gamma in [0.0, -2.2, f32::NAN, f32::INFINITY].iter() {
+            assert_eq!(interpolate_with_gamma(&a, &b, 0.5, *gamma).unwrap(), linear, "gamma {}", gamma);
+        }
+    }
+
+    #[test]
+    fn sequences_can_blend_in_linear_light() {
+        let a = [Point::builder().rgb(0, 0, 0).build()];
+        let b = [Point::builder().rgb(255, 255, 255).build()];
+        let frames = interpolate_sequence_with_gamma(&a, &b, 3, 2.2).unwrap();
+
+        assert_eq!(frames[0], a.to_vec());
+        assert_eq!(frames[1][0].color, Color::new(186, 186, 186));
+        assert_eq!(frames[2], b.to_vec());
+    }
+
+    #[test]
+    fn blends_colors_keeping_positions_of_a() {
+        let a = [Point::builder().position(10, 20).rgb(0, 0, 0).build()];
+        let b = [Point::builder().position(30, 40).rgb(200, 100, 50).build()];
+        let blended = blend_frames(&a, &b, 0.5).unwrap();
+
+        assert_eq!(blended[0].coordinate, a[0].coordinate);
+        assert_eq!(blended[0].color, a[0].blend(&b[0], 0.5).color);
+        assert_eq!(blend_frames(&a, &[], 0.5), Err(LengthMismatch { a: 1, b: 0 }));
+    }
+
+    #[test]
+    fn clamps_points_outside_of_the_coordinate_space() {
+        let mut outside = lit(0, 0);
+        outside.coordinate = Coordinate { x: 0x1000, y: u16::MAX };
+        let mut points = vec![lit(Coordinate::MAX, 0), outside, lit(5, 5)];
+
+        assert_eq!(clamp_all(&mut points), 1);
+        assert_eq!(points, vec![lit(Coordinate::MAX, 0), lit(Coordinate::MAX, Coordinate::MAX), lit(5, 5)]);
+        assert_eq!(clamp_all(&mut points), 0);
+    }
+
+    #[test]
+    fn smoothing_keeps_input_points_and_follows_the_path() {
+        let points = vec![lit(0, 0), lit(1000, 0), lit(2000, 0)];
+        let smoothed = smooth_catmull_rom(&points, 4, usize::MAX);
+
+        assert_eq!(smoothed.len(), 2 * 4 + 1);
+        assert_eq!(smoothed[0], points[0]);
+        assert_eq!(smoothed[4], points[1]);
+        assert_eq!(smoothed[8], points[2]);
+        // a straight line stays straight and moves forward
+        assert!(smoothed.iter().all(|point| point.coordinate.y == 0));
+        assert!(smoothed.windows(2).all(|pair| pair[0].coordinate.x < pair[1].coordinate.x));
+    }
+
+    #[test]
+    fn smoothing_doesnt_cross_blanked_points() {
+        let points = vec![lit(0, 0), lit(1000, 0), dark(1000, 0), dark(0, 1000), lit(0, 1000)];
+        let smoothed = smooth_catmull_rom(&points, 4, usize::MAX);
+
+        assert_eq!(smoothed.len(), points.len() + 3);
+        assert_eq!(smoothed[5..], points[2..]);
+    }
+
+    #[test]
+    fn smoothing_stays_below_max_points() {
+        let points: Vec<Point> = (0..10).map(|i| lit(i * 100, (i % 2) * 100)).collect();
+        let smoothed = smooth_catmull_rom(&points, 16, 40);
+
+        assert!(smoothed.len() <= 40, "{}", smoothed.len());
+        assert!(points.iter().all(|point| smoothed.contains(point)));
+        assert_eq!(smooth_catmull_rom(&points, 16, 0).len(), points.len());
+    }
+
+    #[test]
+    fn analyzes_segments_corners_and_blanking() {
+        let points = vec![lit(0, 0), lit(300, 0), lit(300, 400), lit(0, 0), dark(0, 0)];
+        let stats = analyze(&points);
+
+        assert_eq!(stats.points, 5);
+        assert_eq!(stats.max_segment_length, 500.0);
+        // the turn back at (300, 400) is sharper than a right angle, the right angle itself isn't counted
+        assert_eq!(stats.sharp_corners, 1);
+        assert_eq!(stats.blanked_ratio, 0.2);
+        assert!(stats.galvo_stress > 0.0);
+    }
+
+    #[test]
+    fn straight_paths_dont_stress_the_galvos() {
+        let points: Vec<Point> = (0..10).map(|i| lit(i * 10, i * 10)).collect();
+
+        assert_eq!(analyze(&points).galvo_stress, 0.0);
+        assert_eq!(analyze(&[]), FrameStats::default());
+    }
+
+    #[test]
+    fn lead_in_and_out_surround_the_lit_points() {
+        let mut points = vec![dark(0, 0), lit(100, 100), lit(200, 200), dark(300, 300)];
+
+        assert_eq!(add_lead_in(&mut points, 3), 3);
+        assert_eq!(add_lead_out(&mut points, 2), 2);
+        assert_eq!(
+            points,
+            vec![
+                dark(0, 0),
+                dark(100, 100),
+                dark(100, 100),
+                dark(100, 100),
+                lit(100, 100),
+                lit(200, 200),
+                dark(200, 200),
+                dark(200, 200),
+                dark(300, 300),
+            ]
+        );
+    }
+
+    #[test]
+    fn lead_in_and_out_are_idempotent() {
+        let mut points = vec![lit(100, 100), lit(200, 200)];
+        add_lead_in(&mut points, 4);
+        add_lead_out(&mut points, 4);
+        let once = points.clone();
+
+        assert_eq!(add_lead_in(&mut points, 4), 0);
+        assert_eq!(add_lead_out(&mut points, 4), 0);
+        assert_eq!(points, once);
+        // raising the count only adds the missing points
+        assert_eq!(add_lead_in(&mut points, 6), 2);
+    }
+
+    #[test]
+    fn lead_in_of_dark_frames_adds_nothing() {
+        let mut points = vec![dark(0, 0), dark(1, 1)];
+
+        assert_eq!(add_lead_in(&mut points, 3), 0);
+        assert_eq!(add_lead_out(&mut points, 3), 0);
+        assert_eq!(points.len(), 2);
+    }
+
+    /// Square with corners at 0 and 1000 starting and ending in the middle of its bottom edge
+    fn square() -> Vec<Point> {
+        let mut points: Vec<Point> = (5..10).map(|i| lit(i * 100, 0)).collect();
+        points.extend((0..10).map(|i| lit(1000, i * 100)));
+        points.extend((0..10).map(|i| lit(1000 - i * 100, 1000)));
+        points.extend((0..10).map(|i| lit(0, 1000 - i * 100)));
+        points.extend((0..=5).map(|i| lit(i * 100, 0)));
+        points
+    }
+
+    #[test]
+    fn simplify_closed_keeps_the_cycle_closed() {
+        let simplified = simplify_closed(&square(), 1.0);
+
+        assert_eq!(simplified.first(), simplified.last());
+        assert_eq!(simplified.len(), 5);
+        for corner in [lit(0, 0), lit(1000, 0), lit(1000, 1000), lit(0, 1000)].iter() {
+            assert!(simplified.contains(corner), "{:?} is missing", corner);
+        }
+        // the old join in the middle of an edge is simplified away like any other point
+        assert!(!simplified.contains(&lit(500, 0)));
+    }
+
+    #[test]
+    fn simplify_closed_passes_open_paths_to_simplify() {
+        let mut open = square();
+        open.pop();
+        open.pop();
+        open.pop();
+
+        assert_eq!(simplify_closed(&open, 1.0), simplify(&open, 1.0));
+    }
+
+    #[test]
+    fn dedup_collapses_runs_of_duplicates() {
+        let mut points = vec![lit(0, 0), lit(0, 0), lit(1, 0), lit(100, 0), dark(100, 0), dark(100, 0)];
+
+        assert_eq!(dedup_consecutive(&mut points, 1, 0), 3);
+        assert_eq!(points, vec![lit(0, 0), lit(100, 0), dark(100, 0)]);
+    }
+
+    #[test]
+    fn dedup_keeps_dwell_runs() {
+        let mut points = vec![lit(0, 0), lit(0, 0), lit(100, 0), lit(100, 0), lit(100, 0), lit(100, 0)];
+
+        assert_eq!(dedup_consecutive(&mut points, 0, 3), 1);
+        assert_eq!(points, vec![lit(0, 0), lit(100, 0), lit(100, 0), lit(100, 0), lit(100, 0)]);
+    }
+
+    #[test]
+    fn dedup_keeps_color_changes() {
+        let red = Point::builder().rgb(255, 0, 0).build();
+        let mut points = vec![lit(0, 0), red, lit(0, 0)];
+
+        assert_eq!(dedup_consecutive(&mut points, 0, 0), 0);
+        assert_eq!(points.len(), 3);
+    }
+
+    /// Zig zag with many points on each of its straight lines
+    fn zig_zag(lines: u16, points_per_line: u16) -> Vec<Point> {
+        (0..lines * points_per_line)
+            .map(|i| {
+                let (line, step) = (i / points_per_line, i % points_per_line);
+                let y = if line % 2 == 0 { step * 10 } else { (points_per_line - step) * 10 };
+                lit(line * 10 + step, y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn optimize_reports_its_passes() {
+        let mut points = vec![lit(0, 0), lit(0, 0)];
+        points.extend((1..=10).map(|i| lit(i * 10, 0)));
+        points.push(lit(3000, 3000));
+        let optimized = optimize(&points, OptimizeConfig::default());
+
+        assert_eq!(optimized.deduplicated, 1);
+        assert_eq!(optimized.simplified, 9);
+        assert!(optimized.blanking_points > 0);
+        assert_eq!(optimized.epsilon, 1.0);
+        assert_eq!(optimized.points.first(), Some(&lit(0, 0)));
+        assert_eq!(optimized.points.last(), Some(&lit(3000, 3000)));
+    }
+
+    #[test]
+    fn optimize_respects_the_budget() {
+        let points = zig_zag(40, 100);
+        let config = OptimizeConfig {
+            budget: 200,
+            dwell: 0,
+            ..OptimizeConfig::default()
+        };
+        let optimized = optimize(&points, config);
+
+        assert!(optimized.points.len() <= 200, "{}", optimized.points.len());
+        assert!(optimized.epsilon > config.epsilon);
+    }
+
+    #[test]
+    fn optimize_keeps_the_epsilon_when_within_the_budget() {
+        let points = zig_zag(4, 10);
+        let optimized = optimize(&points, OptimizeConfig::default());
+
+        assert_eq!(optimized.epsilon, 1.0);
+        assert!(optimized.points.len() < points.len());
+    }
+
+    #[test]
+    fn resample_spreads_points_evenly() {
+        let points = vec![lit(0, 0), lit(100, 0), lit(1000, 0)];
+        let resampled = resample(&points, 11);
+
+        let expected: Vec<Point> = (0..=10).map(|i| lit(i * 100, 0)).collect();
+        assert_eq!(resampled, expected);
+        assert_eq!(resample(&resampled, 3), vec![lit(0, 0), lit(500, 0), lit(1000, 0)]);
+    }
+
+    #[test]
+    fn resample_keeps_blanked_parts_blanked() {
+        let points = vec![lit(0, 0), lit(400, 0), dark(400, 0), dark(800, 0), lit(800, 0), lit(1200, 0)];
+        let resampled = resample(&points, 13);
+
+        // samples exactly at the ends of the blanked part may be either
+        for point in resampled.iter().filter(|point| point.coordinate.x % 400 != 0) {
+            let x = point.coordinate.x;
+            assert_eq!(point.is_blanked(), x > 400 && x < 800, "{:?}", point);
+        }
+        assert_eq!(resampled.first(), points.first());
+        assert_eq!(resampled.last(), points.last());
+    }
+
+    #[test]
+    fn resample_handles_degenerate_paths() {
+        assert_eq!(resample(&[], 5), vec![]);
+        assert_eq!(resample(&[lit(1, 1)], 3), vec![lit(1, 1); 3]);
+        assert_eq!(resample(&[lit(1, 1), lit(2, 2)], 0), vec![]);
+        assert_eq!(resample(&[lit(1, 1), lit(1, 1), lit(1, 1)], 2), vec![lit(1, 1), lit(1, 1)]);
+    }
+}
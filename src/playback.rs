@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::{Frame, NativeHeliosDac, NativeHeliosError, Point};
+use crate::ilda::FrameReader;
+use crate::scheduler::{Jitter, Scheduler};
+
+type Result<T> = std::result::Result<T, NativeHeliosError>;
+
+/// Loops a list of frames on a dac at a fixed frame rate
+pub struct Playback {
+    dac: NativeHeliosDac,
+    frames: Vec<Vec<Point>>,
+    running: Arc<AtomicBool>,
+    dropped_frames: usize,
+    jitter: Jitter,
+}
+
+/// Stops a running [Playback] from another thread
+#[derive(Clone)]
+pub struct PlaybackHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl PlaybackHandle {
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Playback {
+    pub fn new(dac: NativeHeliosDac, frames: Vec<Vec<Point>>) -> Self {
+        Playback {
+            dac,
+            frames,
+            running: Arc::new(AtomicBool::new(false)),
+            dropped_frames: 0,
+            jitter: Jitter::default(),
+        }
+    }
+
+    pub fn handle(&self) -> PlaybackHandle {
+        PlaybackHandle {
+            running: Arc::clone(&self.running),
+        }
+    }
+
+    /// Loops the frames until stopped through a [PlaybackHandle]
+    ///
+    /// The point rate of each frame is chosen to draw it exactly once per frame at `fps`.
+    /// Frames the dac isn't ready for in time are dropped, see [dropped_frames](Playback::dropped_frames).
+    /// Fails with [NativeHeliosError::InvalidFps] when `fps` can't be scheduled, see [Scheduler::new].
+    pub fn play(&mut self, fps: f32) -> Result<()> {
+        if self.frames.is_empty() {
+            return Ok(());
+        }
+        let mut scheduler = Scheduler::new(fps, self.frames.len())?;
+        self.running.store(true, Ordering::SeqCst);
+        self.dropped_frames = 0;
+        self.jitter = Jitter::default();
+        let mut skipped_frames = 0;
+        while let Some(index) = scheduler.tick() {
+            self.dropped_frames += scheduler.skipped_frames() - skipped_frames;
+            skipped_frames = scheduler.skipped_frames();
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
+            if self.dac.wait_until_ready(scheduler.deadline().saturating_duration_since(Instant::now()))? {
+                self.dac.write_frame(Frame::with_fps(fps, self.frames[index].clone()))?;
+                self.record_sent(&mut scheduler);
+            } else {
+                self.dropped_frames += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Streams the frames of an ild file until stopped through a [PlaybackHandle]
+    ///
+    /// Frames are read one at a time, so the file is never loaded into memory as a whole.
+    /// When the end of the file is reached playback starts over from its beginning, unless `once` is set.
+    /// Frames the dac isn't ready for in time are dropped like in [play](Playback::play).
+    /// If the file can't be read the output of the dac is stopped and the error returned.
+    /// Fails with [NativeHeliosError::InvalidFps] when `fps` can't be scheduled, see [Scheduler::new].
+    pub fn play_ilda(&mut self, path: impl AsRef<Path>, fps: f32, once: bool) -> Result<()> {
+        Scheduler::new(fps, 1)?;
+        let mut reader = BufReader::new(File::open(path)?);
+        self.running.store(true, Ordering::SeqCst);
+        let result = self.stream_ilda(&mut reader, fps, once);
+        if result.is_err() {
+            self.dac.stop()?;
+        }
+
+        result
+    }
+
+    fn stream_ilda(&mut self, reader: &mut BufReader<File>, fps: f32, once: bool) -> Result<()> {
+        let mut scheduler = Scheduler::new(fps, 1)?;
+        self.dropped_frames = 0;
+        self.jitter = Jitter::default();
+        let mut skipped_frames = 0;
+        let mut pending_skips = 0;
+        loop {
+            reader.seek(SeekFrom::Start(0))?;
+            let mut frames = 0;
+            for points in FrameReader::new(&mut *reader) {
+                let points = points?;
+                frames += 1;
+                if pending_skips > 0 {
+                    pending_skips -= 1;
+                    continue;
+                }
+                scheduler.tick();
+                // catch up with the schedule by leaving out the frames which are already over
+                pending_skips = scheduler.skipped_frames() - skipped_frames;
+                self.dropped_frames += pending_skips;
+                skipped_frames = scheduler.skipped_frames();
+                if !self.running.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                if self.dac.wait_until_ready(scheduler.deadline().saturating_duration_since(Instant::now()))? {
+                    self.dac.write_frame(Frame::with_fps(fps, points))?;
+                    self.record_sent(&mut scheduler);
+                } else {
+                    self.dropped_frames += 1;
+                }
+            }
+            if once || frames == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn record_sent(&mut self, scheduler: &mut Scheduler) {
+        if let Some(sent) = self.dac.stats().last_write() {
+            scheduler.record_sent(sent);
+            self.jitter = scheduler.jitter();
+        }
+    }
+
+    /// Ends playback and stops output of the dac
+    pub fn stop(&mut self) -> Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+        self.dac.stop()
+    }
+
+    /// Amount of frames skipped during the last playback
+    ///
+    /// Frames are skipped when the dac wasn't ready in time or writing the previous frame took too long.
+    /// The count starts over with every call to [play](Playback::play) or [play_ilda](Playback::play_ilda).
+    pub fn dropped_frames(&self) -> usize {
+        self.dropped_frames
+    }
+
+    /// Delays between the time frames were due and the time they were written during the last playback
+    pub fn jitter(&self) -> Jitter {
+        self.jitter
+    }
+
+    pub fn into_inner(self) -> NativeHeliosDac {
+        self.dac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::MockDac;
+
+    fn play_for(playback: &mut Playback, duration: Duration) {
+        let handle = playback.handle();
+        let stopper = thread::spawn(move || {
+            thread::sleep(duration);
+            handle.stop();
+        });
+        playback.play(100.0).unwrap();
+        stopper.join().unwrap();
+    }
+
+    #[test]
+    fn dropped_frames_start_over_with_every_playback() {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let point = Point::builder().position(0x800, 0x800).build();
+        let mut playback = Playback::new(dac, vec![vec![point; 100]]);
+
+        mock.set_ready(false);
+        play_for(&mut playback, Duration::from_millis(100));
+        assert!(playback.dropped_frames() > 0);
+
+        mock.set_ready(true);
+        play_for(&mut playback, Duration::from_millis(20));
+        assert_eq!(playback.dropped_frames(), 0);
+        assert!(!mock.frames().is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+//! Wire format of the helios usb protocol
+//!
+//! Frames are sent as bulk transfers, all other commands and their responses as interrupt transfers.
+//! The types in here encode and decode these transfers without a connected dac.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::{Color, Coordinate, DeviceStatus, FirmwareVersion, Frame, Point, WriteFrameFlags};
+
+// Interrupt control bytes
+pub(crate) const CONTROL_STOP: u8 = 0x01;
+pub(crate) const CONTROL_SET_SHUTTER: u8 = 0x02;
+pub(crate) const CONTROL_GET_STATUS: u8 = 0x03;
+pub(crate) const CONTROL_GET_FIRMWARE_VERSION: u8 = 0x04;
+pub(crate) const CONTROL_GET_NAME: u8 = 0x05;
+pub(crate) const CONTROL_SET_NAME: u8 = 0x06;
+pub(crate) const CONTROL_SEND_SDK_VERSION: u8 = 0x07;
+
+// Interrupt response bytes
+pub(crate) const RESPONSE_GET_STATUS: u8 = 0x83;
+pub(crate) const RESPONSE_GET_FIRMWARE_VERSION: u8 = 0x84;
+pub(crate) const RESPONSE_GET_NAME: u8 = 0x85;
+
+/// Max length of a dac name in bytes
+pub const MAX_NAME_LENGTH: usize = 30;
+
+/// Size of the buffer holding an encoded [Command]
+pub const COMMAND_BUFFER_SIZE: usize = MAX_NAME_LENGTH + 2;
+
+/// Size of a single encoded point
+pub const POINT_SIZE: usize = 7;
+/// Size of the pps, point count and flags following the points of a frame
+pub const FRAME_FOOTER_SIZE: usize = 5;
+
+/// Appends the wire representation
+pub trait WriteToBytes {
+    fn write_to_bytes(&self, buffer: &mut Vec<u8>);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write_to_bytes(&mut buffer);
+
+        buffer
+    }
+}
+
+/// Parses the wire representation
+pub trait ReadFromBytes: Sized {
+    fn read_from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError>;
+}
+
+/// Control message sent to the dac
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Stops output
+    Stop,
+    /// Opens or closes the shutter
+    SetShutter(bool),
+    /// Requests a [Response::Status]
+    GetStatus,
+    /// Requests a [Response::FirmwareVersion]
+    GetFirmwareVersion,
+    /// Requests a [Response::Name]
+    GetName,
+    /// Stores a new name, names longer than [MAX_NAME_LENGTH] bytes are truncated
+    SetName(String),
+    /// Tells the dac which sdk version the host implements
+    SendSdkVersion(u8),
+}
+
+impl WriteToBytes for Command {
+    fn write_to_bytes(&self, buffer: &mut Vec<u8>) {
+        let (bytes, size) = encode_command(self);
+        buffer.extend_from_slice(&bytes[0..size]);
+    }
+}
+
+impl ReadFromBytes for Command {
+    fn read_from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        match bytes {
+            [CONTROL_STOP, ..] => Ok(Command::Stop),
+            [CONTROL_SET_SHUTTER, 0, ..] => Ok(Command::SetShutter(false)),
+            [CONTROL_SET_SHUTTER, 1, ..] => Ok(Command::SetShutter(true)),
+            [CONTROL_SET_SHUTTER, value, ..] => Err(ProtocolError::InvalidValue(*value)),
+            [CONTROL_GET_STATUS, ..] => Ok(Command::GetStatus),
+            [CONTROL_GET_FIRMWARE_VERSION, ..] => Ok(Command::GetFirmwareVersion),
+            [CONTROL_GET_NAME, ..] => Ok(Command::GetName),
+            [CONTROL_SET_NAME, name @ ..] => Ok(Command::SetName(read_name(name)?)),
+            [CONTROL_SEND_SDK_VERSION, version, ..] => Ok(Command::SendSdkVersion(*version)),
+            [command, ..] => Err(ProtocolError::UnknownCommand(*command)),
+            [] => Err(ProtocolError::InvalidLength(0)),
+        }
+    }
+}
+
+/// Answer of the dac to a [Command]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Status(DeviceStatus),
+    FirmwareVersion(FirmwareVersion),
+    Name(String),
+}
+
+impl WriteToBytes for Response {
+    fn write_to_bytes(&self, buffer: &mut Vec<u8>) {
+        match self {
+            Response::Status(status) => buffer.extend_from_slice(&[RESPONSE_GET_STATUS, status.ready() as u8]),
+            Response::FirmwareVersion(version) => {
+                buffer.push(RESPONSE_GET_FIRMWARE_VERSION);
+                buffer.extend_from_slice(&version.number().to_le_bytes());
+            }
+            Response::Name(name) => {
+                buffer.push(RESPONSE_GET_NAME);
+                buffer.extend_from_slice(name.as_bytes());
+                buffer.push(0);
+            }
+        }
+    }
+}
+
+impl ReadFromBytes for Response {
+    fn read_from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        match bytes {
+            [RESPONSE_GET_STATUS, 0, ..] => Ok(Response::Status(DeviceStatus::NotReady)),
+            [RESPONSE_GET_STATUS, 1, ..] => Ok(Response::Status(DeviceStatus::Ready)),
+            [RESPONSE_GET_STATUS, value, ..] => Err(ProtocolError::InvalidValue(*value)),
+            [RESPONSE_GET_FIRMWARE_VERSION, b0, b1, b2, b3, ..] => {
+                Ok(Response::FirmwareVersion(u32::from_le_bytes([*b0, *b1, *b2, *b3]).into()))
+            }
+            [RESPONSE_GET_NAME, name @ ..] => Ok(Response::Name(read_name(name)?)),
+            [RESPONSE_GET_STATUS, ..] | [RESPONSE_GET_FIRMWARE_VERSION, ..] => Err(ProtocolError::InvalidLength(bytes.len())),
+            [response, ..] => Err(ProtocolError::UnknownCommand(*response)),
+            [] => Err(ProtocolError::InvalidLength(0)),
+        }
+    }
+}
+
+/// Encodes a point into 7 bytes: both axes as 12 bits, x first, followed by red, green, blue and intensity
+///
+/// For example (0xABC, 0x123) with color (1, 2, 3) and intensity 4 is sent as `AB C1 23 01 02 03 04`,
+/// (0xFFF, 0xFFF) in white at full intensity as `FF FF FF FF FF FF FF`.
+impl WriteToBytes for Point {
+    fn write_to_bytes(&self, buffer: &mut Vec<u8>) {
+        buffer.push((self.coordinate.x >> 4) as u8);
+        buffer.push(((self.coordinate.x & 0x0F) << 4) as u8 | ((self.coordinate.y >> 8) & 0x0F) as u8);
+        buffer.push((self.coordinate.y & 0xFF) as u8);
+        buffer.push(self.color.r);
+        buffer.push(self.color.g);
+        buffer.push(self.color.b);
+        buffer.push(self.intensity);
+    }
+}
+
+impl ReadFromBytes for Point {
+    fn read_from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let bytes = <[u8; POINT_SIZE]>::try_from(bytes).map_err(|_| ProtocolError::InvalidLength(bytes.len()))?;
+
+        Ok(Point {
+            coordinate: Coordinate {
+                x: (u16::from(bytes[0]) << 4) | (u16::from(bytes[1]) >> 4),
+                y: ((u16::from(bytes[1]) & 0x0F) << 8) | u16::from(bytes[2]),
+            },
+            color: Color::new(bytes[3], bytes[4], bytes[5]),
+            intensity: bytes[6],
+        })
+    }
+}
+
+impl WriteToBytes for Frame {
+    fn write_to_bytes(&self, buffer: &mut Vec<u8>) {
+        write_frame(buffer, &self.points, self.pps, self.points.len(), self.flags);
+    }
+}
+
+/// Parses a frame, points following the announced point count are ignored
+impl ReadFromBytes for Frame {
+    fn read_from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let footer_start = bytes.len()
+            .checked_sub(FRAME_FOOTER_SIZE)
+            .filter(|points| points % POINT_SIZE == 0)
+            .ok_or(ProtocolError::InvalidLength(bytes.len()))?;
+        let (points, footer) = bytes.split_at(footer_start);
+        let pps = u32::from(footer[0]) | (u32::from(footer[1]) << 8);
+        let point_count = usize::from(footer[2]) | (usize::from(footer[3]) << 8);
+        let flags = WriteFrameFlags::from_bits_truncate(footer[4]);
+        if point_count > points.len() / POINT_SIZE {
+            return Err(ProtocolError::InvalidLength(bytes.len()));
+        }
+
+        let points = points
+            .chunks_exact(POINT_SIZE)
+            .take(point_count)
+            .map(Point::read_from_bytes)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Frame::new_with_flags(pps, points, flags))
+    }
+}
+
+/// Encodes a command without allocating, returns the buffer and the length of the message
+pub fn encode_command(command: &Command) -> ([u8; COMMAND_BUFFER_SIZE], usize) {
+    let mut buffer = [0u8; COMMAND_BUFFER_SIZE];
+    let (control, value) = match command {
+        Command::Stop => (CONTROL_STOP, 0),
+        Command::SetShutter(open) => (CONTROL_SET_SHUTTER, *open as u8),
+        Command::GetStatus => (CONTROL_GET_STATUS, 0),
+        Command::GetFirmwareVersion => (CONTROL_GET_FIRMWARE_VERSION, 0),
+        Command::GetName => (CONTROL_GET_NAME, 0),
+        Command::SetName(name) => {
+            // the name is zero padded and always followed by a null byte
+            let name = &name.as_bytes()[..name.len().min(MAX_NAME_LENGTH)];
+            buffer[0] = CONTROL_SET_NAME;
+            buffer[1..=name.len()].copy_from_slice(name);
+
+            return (buffer, COMMAND_BUFFER_SIZE);
+        }
+        Command::SendSdkVersion(version) => (CONTROL_SEND_SDK_VERSION, *version),
+    };
+    buffer[0] = control;
+    buffer[1] = value;
+
+    (buffer, 2)
+}
+
+/// Decodes a response read from the dac, see [Response::read_from_bytes]
+pub fn decode_response(bytes: &[u8]) -> Result<Response, ProtocolError> {
+    Response::read_from_bytes(bytes)
+}
+
+/// Encodes the points of a frame followed by its footer
+///
+/// `point_count` can be lower than the amount of points to make the dac skip trailing points.
+pub(crate) fn write_frame(buffer: &mut Vec<u8>, points: &[Point], pps: u32, point_count: usize, flags: WriteFrameFlags) {
+    buffer.reserve(points.len() * POINT_SIZE + FRAME_FOOTER_SIZE);
+    for point in points {
+        point.write_to_bytes(buffer);
+    }
+    write_frame_footer(buffer, pps, point_count, flags);
+}
+
+/// Encodes the pps, point count and flags following the points of a frame
+pub(crate) fn write_frame_footer(buffer: &mut Vec<u8>, pps: u32, point_count: usize, flags: WriteFrameFlags) {
+    buffer.push((pps & 0xFF) as u8);
+    buffer.push((pps >> 8) as u8);
+    buffer.push((point_count & 0xFF) as u8);
+    buffer.push((point_count >> 8) as u8);
+    buffer.push(flags.bits());
+}
+
+/// Reads a null terminated name
+fn read_name(bytes: &[u8]) -> Result<String, ProtocolError> {
+    let length = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+
+    String::from_utf8(bytes[..length].to_vec()).map_err(|_| ProtocolError::InvalidName)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// Message is too short or too long, contains the length of the message
+    InvalidLength(usize),
+    /// Message starts with an unknown command or response byte
+    UnknownCommand(u8),
+    /// Message contains a value which is out of range for its field
+    InvalidValue(u8),
+    /// Name is not valid utf8
+    InvalidName,
+}
+
+impl core::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ProtocolError::InvalidLength(length) => write!(f, "message has invalid length of {} bytes", length),
+            ProtocolError::UnknownCommand(command) => write!(f, "unknown command {:#04x}", command),
+            ProtocolError::InvalidValue(value) => write!(f, "invalid value {:#04x}", value),
+            ProtocolError::InvalidName => write!(f, "name is not valid utf8"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProtocolError {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn point(x: u16, y: u16, r: u8, g: u8, b: u8, intensity: u8) -> Point {
+        Point {
+            coordinate: Coordinate { x, y },
+            color: Color::new(r, g, b),
+            intensity,
+        }
+    }
+
+    #[test]
+    fn encodes_points() {
+        let vectors = [
+            (point(0xABC, 0x123, 1, 2, 3, 4), [0xAB, 0xC1, 0x23, 0x01, 0x02, 0x03, 0x04]),
+            (point(0, 0, 0, 0, 0, 0), [0x00; POINT_SIZE]),
+            (point(0, 0, 255, 255, 255, 255), [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF]),
+            (point(0xFFF, 0xFFF, 0, 0, 0, 0), [0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00]),
+            (point(0xFFF, 0xFFF, 255, 255, 255, 255), [0xFF; POINT_SIZE]),
+            (point(0xFFF, 0, 0, 0, 0, 0), [0xFF, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            (point(0, 0xFFF, 0, 0, 0, 0), [0x00, 0x0F, 0xFF, 0x00, 0x00, 0x00, 0x00]),
+        ];
+
+        for (point, bytes) in vectors.iter() {
+            assert_eq!(point.to_bytes(), bytes, "{:?}", point);
+            assert_eq!(Point::read_from_bytes(bytes), Ok(*point));
+        }
+    }
+
+    #[test]
+    fn y_doesnt_overflow_into_x() {
+        assert_eq!(point(0, 0x1FFF, 0, 0, 0, 0).to_bytes()[..3], [0x00, 0x0F, 0xFF]);
+    }
+
+    #[test]
+    fn encodes_frame_footer_little_endian() {
+        let mut buffer = Vec::new();
+        write_frame_footer(&mut buffer, 1000, 3, WriteFrameFlags::SINGLE_MODE);
+        assert_eq!(buffer, [0xE8, 0x03, 0x03, 0x00, 0x02]);
+
+        buffer.clear();
+        write_frame_footer(&mut buffer, 0xFFFF, Frame::MAX_POINTS, WriteFrameFlags::all());
+        assert_eq!(buffer, [0xFF, 0xFF, 0x00, 0x10, 0x07]);
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let points = vec![
+            point(0, 0, 0, 0, 0, 0),
+            point(0xABC, 0x123, 1, 2, 3, 4),
+            point(0xFFF, 0xFFF, 255, 255, 255, 255),
+        ];
+        for pps in [Frame::MIN_PPS, 1000, 0x1234, Frame::MAX_PPS].iter() {
+            let frame = Frame::new_with_flags(*pps, points.clone(), WriteFrameFlags::START_IMMEDIATELY);
+            let bytes = frame.to_bytes();
+
+            assert_eq!(bytes.len(), points.len() * POINT_SIZE + FRAME_FOOTER_SIZE);
+            assert_eq!(bytes[bytes.len() - FRAME_FOOTER_SIZE..][..4], [(pps & 0xFF) as u8, (pps >> 8) as u8, 3, 0]);
+            assert_eq!(Frame::read_from_bytes(&bytes), Ok(frame));
+        }
+    }
+
+    #[test]
+    fn ignores_points_after_point_count() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &[point(1, 2, 3, 4, 5, 6), point(7, 8, 9, 10, 11, 12)], 1000, 1, WriteFrameFlags::empty());
+
+        assert_eq!(Frame::read_from_bytes(&buffer), Ok(Frame::new(1000, vec![point(1, 2, 3, 4, 5, 6)])));
+    }
+
+    #[test]
+    fn rejects_frames_announcing_missing_points() {
+        let mut buffer = point(1, 2, 3, 4, 5, 6).to_bytes();
+        write_frame_footer(&mut buffer, 1000, 2, WriteFrameFlags::empty());
+
+        assert_eq!(Frame::read_from_bytes(&buffer), Err(ProtocolError::InvalidLength(buffer.len())));
+    }
+
+    #[test]
+    fn firmware_version_round_trips() {
+        let response = Response::FirmwareVersion(FirmwareVersion::new(0x0102_0304));
+        let bytes = response.to_bytes();
+
+        assert_eq!(bytes, [RESPONSE_GET_FIRMWARE_VERSION, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(decode_response(&bytes), Ok(response));
+    }
+
+    #[test]
+    fn rejects_points_with_invalid_length() {
+        assert_eq!(Point::read_from_bytes(&[0; POINT_SIZE - 1]), Err(ProtocolError::InvalidLength(POINT_SIZE - 1)));
+    }
+}
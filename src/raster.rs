@@ -0,0 +1,50 @@
+//! Raster scanning of bitmaps
+
+use image::RgbImage;
+
+use crate::frame::{Color, Coordinate, Point};
+
+/// Scans an image line by line, alternating the direction of each line
+///
+/// The image is downsampled evenly on both axes until it fits into `max_points` points.
+/// It is scaled to fill the coordinate space while keeping its aspect ratio, the top row of the image ends up at the top.
+/// Black pixels are blanked.
+pub fn image_to_points(image: &RgbImage, max_points: usize) -> Vec<Point> {
+    let (width, height) = image.dimensions();
+    let pixels = width as usize * height as usize;
+    if pixels == 0 || max_points == 0 {
+        return Vec::new();
+    }
+    let mut step = ((pixels as f32 / max_points as f32).sqrt().ceil() as u32).max(1);
+    // rounding the step can leave slightly too many samples
+    while width.div_ceil(step) as usize * height.div_ceil(step) as usize > max_points {
+        step += 1;
+    }
+    let (columns, rows) = (width.div_ceil(step), height.div_ceil(step));
+
+    let scale = f32::from(Coordinate::MAX) / (columns.max(rows) - 1).max(1) as f32;
+    let offset_x = (f32::from(Coordinate::MAX) - (columns - 1) as f32 * scale) / 2.0;
+    let offset_y = (f32::from(Coordinate::MAX) - (rows - 1) as f32 * scale) / 2.0;
+
+    let mut points = Vec::with_capacity(columns as usize * rows as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let column = if row % 2 == 0 { column } else { columns - 1 - column };
+            let pixel = image.get_pixel(column * step, row * step);
+            let mut point = Point {
+                coordinate: Coordinate {
+                    x: (offset_x + column as f32 * scale).round() as u16,
+                    y: Coordinate::MAX - (offset_y + row as f32 * scale).round() as u16,
+                },
+                color: Color::new(pixel[0], pixel[1], pixel[2]),
+                intensity: 0xFF,
+            };
+            if point.is_blanked() {
+                point.blank();
+            }
+            points.push(point);
+        }
+    }
+
+    points
+}
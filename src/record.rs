@@ -0,0 +1,432 @@
+//! Compact recordings of the frames written to a dac
+//!
+//! Each frame is stored with its pps, flags and the time passed since the previous frame, so a session
+//! can be replayed later. Positions are stored as deltas to the previous point, colors only when they change
+//! and repeated points, like blanked dwell points, as runs.
+//! Different from ILDA files the recording keeps every point exactly as it was written.
+//!
+//! Frames are recorded as the application wrote them, not as they were sent over USB:
+//! safety scaling, calibration and the workaround for frames of 45 + 64n points are applied again
+//! by the dac a recording is replayed on. This keeps recordings independent of the dac they were made with,
+//! so a recording replayed with different limits or a different calibration follows those instead.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Color, Coordinate, Frame, NativeHeliosDac, NativeHeliosError, Point, WriteFrameFlags};
+
+type Result<T> = std::result::Result<T, NativeHeliosError>;
+
+const MAGIC: &[u8; 4] = b"HDRC";
+const VERSION: u8 = 1;
+
+/// Point with its own color, followed by the position deltas and the color
+const TAG_POINT: u8 = 0;
+/// Point with the color of the previous point, followed by the position deltas
+const TAG_SAME_COLOR: u8 = 1;
+/// Repeats the previous point, followed by the amount of repetitions
+const TAG_REPEAT: u8 = 2;
+
+/// Writes frames to a dac while recording them
+///
+/// Frames are recorded as passed to [write_frame](Recorder::write_frame), before safety scaling, calibration
+/// and the 45 + 64n points workaround, so replaying them on a dac with the same settings sends the same data.
+pub struct Recorder<W> {
+    dac: NativeHeliosDac,
+    writer: W,
+    last_write: Option<Instant>,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a recording by writing its header
+    pub fn new(dac: NativeHeliosDac, mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        Ok(Recorder {
+            dac,
+            writer,
+            last_write: None,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Writes a frame to the dac and records it once it was sent
+    pub fn write_frame(&mut self, frame: Frame) -> Result<()> {
+        self.dac.write_frame_ref(&frame)?;
+        let now = Instant::now();
+        let delay = self.last_write.map(|last_write| now - last_write).unwrap_or_default();
+        self.last_write = Some(now);
+        self.buffer.clear();
+        encode_frame(&mut self.buffer, delay, &frame);
+        self.writer.write_all(&self.buffer)?;
+
+        Ok(())
+    }
+
+    pub fn dac(&mut self) -> &mut NativeHeliosDac {
+        &mut self.dac
+    }
+
+    /// Flushes the recording and returns the dac and the writer
+    pub fn into_inner(mut self) -> io::Result<(NativeHeliosDac, W)> {
+        self.writer.flush()?;
+
+        Ok((self.dac, self.writer))
+    }
+}
+
+/// Reads a recording made by a [Recorder]
+pub struct Player<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> Player<R> {
+    /// Checks the header of the recording
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("missing recording header"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported recording version"));
+        }
+
+        Ok(Player { reader, done: false })
+    }
+
+    /// Writes all recorded frames to `dac` with the timing they were recorded with
+    pub fn play(&mut self, dac: &mut NativeHeliosDac) -> Result<()> {
+        let start = Instant::now();
+        let mut offset = Duration::ZERO;
+        for frame in self {
+            let (delay, frame) = frame?;
+            offset += delay;
+            if let Some(remaining) = (start + offset).checked_duration_since(Instant::now()) {
+                thread::sleep(remaining);
+            }
+            dac.write_frame(frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<(Duration, Frame)>> {
+        let delay = match read_first_varint(&mut self.reader)? {
+            Some(delay) => Duration::from_micros(delay),
+            None => return Ok(None),
+        };
+        let pps = u32::try_from(read_varint(&mut self.reader)?).map_err(|_| invalid_data("pps is out of range"))?;
+        let flags = WriteFrameFlags::from_bits_truncate(read_u8(&mut self.reader)?);
+        let count = read_varint(&mut self.reader)? as usize;
+        let mut points: Vec<Point> = Vec::with_capacity(count.min(Frame::MAX_POINTS));
+        let mut previous = Point {
+            coordinate: Coordinate { x: 0, y: 0 },
+            color: Color::new(0, 0, 0),
+            intensity: 0,
+        };
+        while points.len() < count {
+            match read_u8(&mut self.reader)? {
+                tag @ (TAG_POINT | TAG_SAME_COLOR) => {
+                    let x = apply_delta(previous.coordinate.x, read_varint(&mut self.reader)?)?;
+                    let y = apply_delta(previous.coordinate.y, read_varint(&mut self.reader)?)?;
+                    previous.coordinate = Coordinate { x, y };
+                    if tag == TAG_POINT {
+                        let mut color = [0u8; 4];
+                        self.reader.read_exact(&mut color)?;
+                        previous.color = Color::new(color[0], color[1], color[2]);
+                        previous.intensity = color[3];
+                    }
+                    points.push(previous);
+                }
+                TAG_REPEAT => {
+                    let repetitions = read_varint(&mut self.reader)? as usize;
+                    if points.is_empty() || repetitions > count - points.len() {
+                        return Err(invalid_data("invalid run of repeated points"));
+                    }
+                    points.extend(std::iter::repeat_n(previous, repetitions));
+                }
+                tag => return Err(invalid_data(&format!("unknown point tag {}", tag))),
+            }
+        }
+
+        Ok(Some((delay, Frame::new_with_flags(pps, points, flags))))
+    }
+}
+
+/// Yields the recorded frames with the time passed since the previous frame
+impl<R: Read> Iterator for Player<R> {
+    type Item = io::Result<(Duration, Frame)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame().transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        frame
+    }
+}
+
+fn encode_frame(buffer: &mut Vec<u8>, delay: Duration, frame: &Frame) {
+    write_varint(buffer, delay.as_micros() as u64);
+    write_varint(buffer, u64::from(frame.pps));
+    buffer.push(frame.flags.bits());
+    write_varint(buffer, frame.points.len() as u64);
+
+    let mut previous: Option<&Point> = None;
+    let mut points = frame.points.iter().peekable();
+    while let Some(point) = points.next() {
+        if previous == Some(point) {
+            let mut repetitions = 1;
+            while points.next_if_eq(&point).is_some() {
+                repetitions += 1;
+            }
+            buffer.push(TAG_REPEAT);
+            write_varint(buffer, repetitions);
+            continue;
+        }
+        let (x, y, same_color) = match previous {
+            Some(previous) => (
+                previous.coordinate.x,
+                previous.coordinate.y,
+                previous.color == point.color && previous.intensity == point.intensity,
+            ),
+            None => (0, 0, false),
+        };
+        buffer.push(if same_color { TAG_SAME_COLOR } else { TAG_POINT });
+        write_varint(buffer, zigzag(i32::from(point.coordinate.x) - i32::from(x)));
+        write_varint(buffer, zigzag(i32::from(point.coordinate.y) - i32::from(y)));
+        if !same_color {
+            buffer.extend_from_slice(&[point.color.r, point.color.g, point.color.b, point.intensity]);
+        }
+        previous = Some(point);
+    }
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn apply_delta(value: u16, delta: u64) -> io::Result<u16> {
+    let delta = ((delta >> 1) as i64) ^ -((delta & 1) as i64);
+
+    u16::try_from(i64::from(value) + delta).map_err(|_| invalid_data("coordinate is out of range"))
+}
+
+/// Writes an unsigned LEB128 number
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    read_first_varint(reader)?.ok_or_else(|| io::ErrorKind::UnexpectedEof.into())
+}
+
+/// Reads an unsigned LEB128 number, returns `None` when the reader ended before its first byte
+fn read_first_varint(reader: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        value |= u64::from(byte[0] & 0x7F) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+
+    Err(invalid_data("number is too long"))
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+
+    Ok(byte[0])
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockDac;
+
+    fn point(x: u16, y: u16, r: u8, g: u8, b: u8) -> Point {
+        Point {
+            coordinate: Coordinate { x, y },
+            color: Color::new(r, g, b),
+            intensity: 255,
+        }
+    }
+
+    fn recorded_frames() -> Vec<Frame> {
+        let dwell = point(0xFFF, 0, 0, 0, 0);
+        let mut dwelling = vec![point(0, 0xFFF, 255, 0, 0), point(0x800, 0x800, 255, 0, 0)];
+        dwelling.extend(std::iter::repeat_n(dwell, 20));
+        dwelling.push(point(0x7FF, 0xFFF, 0, 255, 0));
+
+        vec![
+            Frame::new(30_000, vec![point(10, 20, 255, 255, 255), point(0, 0, 255, 255, 255), point(4000, 5, 1, 2, 3)]),
+            Frame::new_with_flags(12_000, dwelling, WriteFrameFlags::SINGLE_MODE),
+            Frame::new(30_000, Vec::new()),
+        ]
+    }
+
+    fn record(frames: &[Frame]) -> (MockDac, Vec<u8>) {
+        let mock = MockDac::new();
+        let dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        let mut recorder = Recorder::new(dac, Vec::new()).unwrap();
+        for frame in frames {
+            recorder.write_frame(frame.clone()).unwrap();
+        }
+        let (_, recording) = recorder.into_inner().unwrap();
+
+        (mock, recording)
+    }
+
+    fn encoded(frame: &Frame) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        encode_frame(&mut buffer, Duration::ZERO, frame);
+
+        buffer
+    }
+
+    #[test]
+    fn replaying_a_recording_sends_the_recorded_frames() {
+        let frames = recorded_frames();
+        let (recorded, recording) = record(&frames);
+        assert!(recorded.is_stopped());
+
+        let mock = MockDac::new();
+        let mut dac = NativeHeliosDac::from_transport(mock.clone()).unwrap();
+        Player::new(&recording[..]).unwrap().play(&mut dac).unwrap();
+
+        assert_eq!(mock.frames().len(), 2);
+        assert_eq!(mock.frames(), recorded.frames());
+        // the empty frame stopped the output again
+        assert!(mock.is_stopped());
+    }
+
+    #[test]
+    fn recordings_read_back_the_recorded_frames() {
+        let frames = recorded_frames();
+        let (_, recording) = record(&frames);
+
+        let replayed = Player::new(&recording[..])
+            .unwrap()
+            .map(|frame| frame.map(|(_, frame)| frame))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(replayed, frames);
+    }
+
+    #[test]
+    fn repeated_points_are_stored_as_a_run() {
+        let dwell = point(100, 100, 0, 0, 0);
+        let frame = Frame::new(30_000, vec![dwell; 200]);
+
+        let buffer = encoded(&frame);
+        // delay, pps as 3 bytes, flags, count as 2 bytes, the first point and a single run
+        assert_eq!(buffer.len(), 1 + 3 + 1 + 2 + (1 + 2 + 2 + 4) + (1 + 2));
+        assert_eq!(&buffer[buffer.len() - 3..], &[TAG_REPEAT, 199 | 0x80, 1]);
+    }
+
+    #[test]
+    fn points_with_the_color_of_their_predecessor_only_store_deltas() {
+        let frame = Frame::new(30_000, vec![point(10, 10, 1, 2, 3), point(5, 12, 1, 2, 3)]);
+
+        let buffer = encoded(&frame);
+        assert_eq!(&buffer[buffer.len() - 3..], &[TAG_SAME_COLOR, zigzag(-5) as u8, zigzag(2) as u8]);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for (value, length) in [(0, 1), (0x7F, 1), (0x80, 2), (300, 2), (0x3FFF, 2), (0x4000, 3), (u64::MAX, 10)] {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, value);
+            assert_eq!(buffer.len(), length, "length of {}", value);
+            assert_eq!(read_varint(&mut &buffer[..]).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn varints_that_are_too_long_are_rejected() {
+        let buffer = [0x80; 11];
+        assert_eq!(read_varint(&mut &buffer[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn zigzag_deltas_round_trip() {
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+        assert_eq!(zigzag(-2), 3);
+        for (from, to) in [(0u16, 0xFFFu16), (0xFFF, 0), (0x800, 0x7FF), (0x7FF, 0x800), (5, 5)] {
+            let delta = zigzag(i32::from(to) - i32::from(from));
+            assert_eq!(apply_delta(from, delta).unwrap(), to);
+        }
+    }
+
+    #[test]
+    fn deltas_leaving_the_coordinate_range_are_rejected() {
+        assert!(apply_delta(0, zigzag(-1)).is_err());
+        assert!(apply_delta(u16::MAX, zigzag(1)).is_err());
+    }
+
+    #[test]
+    fn truncated_recordings_are_rejected() {
+        let (_, recording) = record(&recorded_frames()[..1]);
+
+        for length in 0..5 {
+            assert!(Player::new(&recording[..length]).is_err(), "header cut to {} bytes", length);
+        }
+        assert_eq!(Player::new(&recording[..5]).unwrap().count(), 0);
+        for length in 6..recording.len() {
+            let mut player = Player::new(&recording[..length]).unwrap();
+            let error = player.next().unwrap().unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "recording cut to {} bytes", length);
+            assert!(player.next().is_none());
+        }
+    }
+
+    #[test]
+    fn invalid_recordings_are_rejected() {
+        assert!(Player::new(&b"HDRX\x01"[..]).is_err());
+        assert!(Player::new(&b"HDRC\x02"[..]).is_err());
+
+        // a run without a point to repeat
+        let recording = [&MAGIC[..], &[VERSION, 0, 100, 0, 2, TAG_REPEAT, 2]].concat();
+        let error = Player::new(&recording[..]).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // a run longer than the frame
+        let recording = [&MAGIC[..], &[VERSION, 0, 100, 0, 2, TAG_POINT, 0, 0, 1, 2, 3, 4, TAG_REPEAT, 2]].concat();
+        let error = Player::new(&recording[..]).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let recording = [&MAGIC[..], &[VERSION, 0, 100, 0, 1, 7]].concat();
+        let error = Player::new(&recording[..]).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,212 @@
+//! Output power limiting
+
+use crate::frame::Point;
+
+/// Limits the brightness of frames before they are sent to the dac
+///
+/// The power of a point is the mean of its color channels weighted by its intensity,
+/// ranging from 0.0 (dark) to 1.0 (white at full intensity).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafetyLimiter {
+    max_average_power: f32,
+    max_point_power: f32,
+}
+
+impl SafetyLimiter {
+    /// Limits the average power of a frame to `max_average_power`
+    ///
+    /// A NaN limit is treated as 0.0, so every frame is scaled to black.
+    pub fn new(max_average_power: f32) -> Self {
+        debug_assert!(!max_average_power.is_nan(), "max average power is NaN");
+        SafetyLimiter {
+            max_average_power: not_nan(max_average_power),
+            max_point_power: 1.0,
+        }
+    }
+
+    /// Rejects frames containing a point with a power above `max_point_power` after scaling
+    ///
+    /// A NaN limit is treated as 0.0, so every frame containing a lit point is rejected.
+    pub fn with_max_point_power(mut self, max_point_power: f32) -> Self {
+        debug_assert!(!max_point_power.is_nan(), "max point power is NaN");
+        self.max_point_power = not_nan(max_point_power);
+        self
+    }
+
+    pub fn max_average_power(&self) -> f32 {
+        self.max_average_power
+    }
+
+    pub fn max_point_power(&self) -> f32 {
+        self.max_point_power
+    }
+
+    /// Calculates the factor the colors of `points` have to be scaled by to stay below the average power limit
+    ///
+    /// The limit holds for the colors scaled with [scale_point], which rounds them down.
+    /// Fails when a single point exceeds the per point limit even after scaling.
+    pub fn scale_for(&self, points: &[Point]) -> Result<f32, SafetyLimitExceeded> {
+        if points.is_empty() {
+            return Ok(1.0);
+        }
+        let average_power = points.iter().map(point_power).sum::<f32>() / points.len() as f32;
+        let mut scale = 1.0;
+        if average_power > self.max_average_power {
+            scale = self.max_average_power / average_power;
+            // the scale itself is rounded, make sure the scaled colors really end up below the limit
+            while scale > 0.0 && scaled_average_power(points, scale) > f64::from(self.max_average_power) {
+                scale *= 1.0 - 1e-6;
+            }
+        }
+
+        for (index, point) in points.iter().enumerate() {
+            let power = point_power(point) * scale;
+            if power > self.max_point_power {
+                return Err(SafetyLimitExceeded {
+                    point: index,
+                    power,
+                    limit: self.max_point_power,
+                });
+            }
+        }
+
+        Ok(scale)
+    }
+}
+
+/// Scales the colors of a point by `scale` as returned by [SafetyLimiter::scale_for]
+///
+/// Channels are rounded down, so the power of the point never ends up above the scaled power.
+/// The `intensity` is left untouched.
+pub fn scale_point(point: &mut Point, scale: f32) {
+    let scale_channel = |value: u8| (f32::from(value) * scale).floor().clamp(0.0, 255.0) as u8;
+    point.color.r = scale_channel(point.color.r);
+    point.color.g = scale_channel(point.color.g);
+    point.color.b = scale_channel(point.color.b);
+}
+
+/// Average power of the points after scaling them with [scale_point], summed up without rounding errors
+fn scaled_average_power(points: &[Point], scale: f32) -> f64 {
+    let total = points
+        .iter()
+        .map(|point| {
+            let mut point = *point;
+            scale_point(&mut point, scale);
+            let color = u64::from(point.color.r) + u64::from(point.color.g) + u64::from(point.color.b);
+            color * u64::from(point.intensity)
+        })
+        .sum::<u64>();
+
+    total as f64 / (3.0 * 255.0 * 255.0 * points.len() as f64)
+}
+
+fn not_nan(value: f32) -> f32 {
+    if value.is_nan() { 0.0 } else { value }
+}
+
+/// Power of a single point from 0.0 to 1.0
+pub fn point_power(point: &Point) -> f32 {
+    let color = f32::from(point.color.r) + f32::from(point.color.g) + f32::from(point.color.b);
+
+    color / (3.0 * 255.0) * f32::from(point.intensity) / 255.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafetyLimitExceeded {
+    /// Index of the offending point
+    pub point: usize,
+    pub power: f32,
+    pub limit: f32,
+}
+
+impl std::fmt::Display for SafetyLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "point {} has a power of {:.3}, max is {:.3}", self.point, self.power, self.limit)
+    }
+}
+
+impl std::error::Error for SafetyLimitExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray() -> Point {
+        Point::builder().position(0, 0).rgb(102, 102, 102).build()
+    }
+
+    fn dark() -> Point {
+        Point::builder().position(0, 0).rgb(0, 0, 0).build()
+    }
+
+    fn white() -> Point {
+        Point::builder().position(0, 0).rgb(255, 255, 255).build()
+    }
+
+    /// Frame with an average power of 0.5
+    fn half_lit() -> Vec<Point> {
+        [white(), dark()].iter().cycle().take(10).copied().collect()
+    }
+
+    #[test]
+    fn frames_at_or_below_the_average_limit_are_not_scaled() {
+        assert_eq!(SafetyLimiter::new(0.51).scale_for(&half_lit()), Ok(1.0));
+        assert_eq!(SafetyLimiter::new(0.5).scale_for(&half_lit()), Ok(1.0));
+    }
+
+    #[test]
+    fn frames_just_above_the_average_limit_are_scaled_below_it() {
+        let points = half_lit();
+        let limiter = SafetyLimiter::new(0.49);
+
+        let scale = limiter.scale_for(&points).unwrap();
+        assert!(scale < 1.0);
+        assert!(scale > 0.9);
+
+        let scaled = points
+            .iter()
+            .map(|point| {
+                let mut point = *point;
+                scale_point(&mut point, scale);
+                point_power(&point)
+            })
+            .sum::<f32>()
+            / points.len() as f32;
+        assert!(scaled <= 0.49, "scaled power {} is above the limit", scaled);
+    }
+
+    #[test]
+    fn points_just_below_the_point_limit_are_accepted() {
+        let points = vec![dark(), dark(), white(), dark()];
+        let limiter = SafetyLimiter::new(1.0).with_max_point_power(1.0);
+
+        assert_eq!(limiter.scale_for(&points), Ok(1.0));
+    }
+
+    #[test]
+    fn points_just_above_the_point_limit_report_their_index() {
+        let points = vec![dark(), gray(), white(), white()];
+        let limiter = SafetyLimiter::new(1.0).with_max_point_power(0.99);
+
+        let error = limiter.scale_for(&points).unwrap_err();
+        assert_eq!(error.point, 2);
+        assert_eq!(error.power, 1.0);
+        assert_eq!(error.limit, 0.99);
+    }
+
+    #[test]
+    fn the_point_limit_applies_after_average_scaling() {
+        // the average of 0.5 is scaled down by half, bringing the white point to 0.5
+        let points = vec![dark(), white()];
+
+        assert!(SafetyLimiter::new(0.25).with_max_point_power(0.5).scale_for(&points).is_ok());
+        let error = SafetyLimiter::new(0.25).with_max_point_power(0.45).scale_for(&points).unwrap_err();
+        assert_eq!(error.point, 1);
+        assert!(error.power <= 0.5);
+    }
+
+    #[test]
+    fn empty_frames_are_never_scaled() {
+        assert_eq!(SafetyLimiter::new(0.0).with_max_point_power(0.0).scale_for(&[]), Ok(1.0));
+    }
+}
@@ -0,0 +1,174 @@
+//! Frame timing
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tells which frame of a looping animation is due
+///
+/// Frame `n` is due `n / fps` seconds after the scheduler was created.
+/// These targets are absolute, so oversleeping delays a single frame without shifting the following ones.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    start: Instant,
+    frame_duration: Duration,
+    frames: usize,
+    next: u64,
+    skipped_frames: usize,
+    jitter: Jitter,
+}
+
+/// Delays between the time frames were due and the time they were sent, see [Scheduler::record_sent]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Jitter {
+    /// Delay of the last recorded frame
+    pub last: Duration,
+    /// Longest delay of all recorded frames
+    pub max: Duration,
+    /// Average delay of all recorded frames
+    pub mean: Duration,
+    /// Amount of recorded frames
+    pub frames: u32,
+    total: Duration,
+}
+
+impl Scheduler {
+    /// Schedules `frames` frames at `fps`, starting now
+    ///
+    /// Fails when `fps` isn't a finite positive number, so small that a frame would last longer than [Duration::MAX]
+    /// or so large that a frame would last less than a nanosecond.
+    pub fn new(fps: f32, frames: usize) -> Result<Self, InvalidFps> {
+        let frame_duration = Some(fps)
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .and_then(|fps| Duration::try_from_secs_f32(1.0 / fps).ok())
+            .filter(|duration| !duration.is_zero())
+            .ok_or(InvalidFps(fps))?;
+
+        Ok(Scheduler {
+            start: Instant::now(),
+            frame_duration,
+            frames,
+            next: 0,
+            skipped_frames: 0,
+            jitter: Jitter::default(),
+        })
+    }
+
+    /// Sleeps until the next frame is due and returns its index
+    ///
+    /// When the caller fell behind, frames which are already over are skipped,
+    /// see [skipped_frames](Scheduler::skipped_frames).
+    /// Returns `None` when there are no frames.
+    pub fn tick(&mut self) -> Option<usize> {
+        if self.frames == 0 {
+            return None;
+        }
+        let now = Instant::now();
+        let target = self.target(self.next);
+        if let Some(remaining) = target.checked_duration_since(now) {
+            thread::sleep(remaining);
+        } else {
+            let due = (now.duration_since(self.start).as_secs_f64() / self.frame_duration.as_secs_f64()) as u64;
+            if due > self.next {
+                self.skipped_frames += (due - self.next) as usize;
+                self.next = due;
+            }
+        }
+        let index = (self.next % self.frames as u64) as usize;
+        self.next += 1;
+
+        Some(index)
+    }
+
+    /// Time at which the frame returned by the last [tick](Scheduler::tick) is over
+    pub fn deadline(&self) -> Instant {
+        self.target(self.next)
+    }
+
+    /// Amount of frames skipped because [tick](Scheduler::tick) was called too late
+    pub fn skipped_frames(&self) -> usize {
+        self.skipped_frames
+    }
+
+    /// Records that the frame returned by the last [tick](Scheduler::tick) was sent at `sent`
+    ///
+    /// Pass the time the frame was actually written, e.g. `last_write` of the stats of the native dac.
+    /// Frames sent before they were due count as sent on time. Does nothing before the first tick.
+    pub fn record_sent(&mut self, sent: Instant) {
+        if self.next == 0 {
+            return;
+        }
+        let delay = sent.saturating_duration_since(self.target(self.next - 1));
+        let jitter = &mut self.jitter;
+        jitter.last = delay;
+        jitter.max = jitter.max.max(delay);
+        jitter.frames += 1;
+        jitter.total += delay;
+        jitter.mean = jitter.total / jitter.frames;
+    }
+
+    /// Delay of the last frame recorded with [record_sent](Scheduler::record_sent)
+    pub fn last_frame_jitter(&self) -> Duration {
+        self.jitter.last
+    }
+
+    /// Delays of all frames recorded with [record_sent](Scheduler::record_sent)
+    pub fn jitter(&self) -> Jitter {
+        self.jitter
+    }
+
+    fn target(&self, tick: u64) -> Instant {
+        self.start + self.frame_duration.mul_f64(tick as f64)
+    }
+}
+
+/// Frame rate which can't be scheduled, see [Scheduler::new]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidFps(pub f32);
+
+impl std::fmt::Display for InvalidFps {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "can't schedule frames at {} fps", self.0)
+    }
+}
+
+impl std::error::Error for InvalidFps {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_frame_rates_which_cant_be_scheduled() {
+        for fps in [0.0, -0.0, -1.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 1e10, 1e30, 1e-40].iter() {
+            assert!(Scheduler::new(*fps, 1).is_err(), "{} fps", fps);
+        }
+        assert!(Scheduler::new(1e-3, 1).is_ok());
+        assert!(Scheduler::new(1e8, 1).is_ok());
+        assert_eq!(Scheduler::new(-1.0, 1).err(), Some(InvalidFps(-1.0)));
+    }
+
+    #[test]
+    fn loops_over_the_frames() {
+        let mut scheduler = Scheduler::new(100.0, 3).unwrap();
+        let indices: Vec<_> = (0..5).map(|_| scheduler.tick().unwrap()).collect();
+
+        assert_eq!(indices, vec![0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn skips_frames_which_are_over() {
+        let mut scheduler = Scheduler::new(1000.0, 1000).unwrap();
+        assert_eq!(scheduler.tick(), Some(0));
+        thread::sleep(Duration::from_millis(20));
+        let index = scheduler.tick().unwrap();
+
+        assert!(index >= 20, "{}", index);
+        assert_eq!(scheduler.skipped_frames(), index - 1);
+        assert!(scheduler.deadline() > Instant::now());
+    }
+
+    #[test]
+    fn without_frames_nothing_is_due() {
+        assert_eq!(Scheduler::new(30.0, 0).unwrap().tick(), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Generators for basic shapes in the 12 bit coordinate space
+//!
+//! Each shape starts and ends with blanked points at its first and last position,
+//! so the scanner can move there and settle before the laser is turned on.
+
+use std::f32::consts::TAU;
+
+use crate::frame::{Color, Coordinate, Point};
+
+/// Maximum amount of segments of a single shape
+pub const MAX_SEGMENTS: usize = 1024;
+/// Amount of blanked points added before and after each shape
+pub const LEAD_POINTS: usize = 4;
+/// Maximum distance between two points on a line in coordinate units
+pub const LINE_STEP: u16 = 64;
+
+/// Closed circle approximated by `segments` straight segments
+///
+/// Parts outside of the coordinate space are clamped to its edges.
+pub fn circle(center: Coordinate, radius: u16, segments: usize, color: Color) -> Result<Vec<Point>, ShapeError> {
+    validate_segments(segments, 3)?;
+    let (cx, cy) = (f32::from(center.x), f32::from(center.y));
+    let radius = f32::from(radius);
+    let vertices = (0..=segments).map(|segment| {
+        let (sin, cos) = (segment as f32 / segments as f32 * TAU).sin_cos();
+        Coordinate {
+            x: clamp(cx + cos * radius),
+            y: clamp(cy + sin * radius),
+        }
+    });
+
+    Ok(with_leads(vertices.map(|coordinate| lit(coordinate, color)).collect()))
+}
+
+/// Straight line from `a` to `b` with points at most [LINE_STEP] units apart
+pub fn line(a: Coordinate, b: Coordinate, color: Color) -> Vec<Point> {
+    let mut points = vec![lit(a, color)];
+    push_edge(&mut points, a, b, color);
+
+    with_leads(points)
+}
+
+/// Closed polygon through all `vertices`, the last vertex is connected back to the first
+pub fn polygon(vertices: &[Coordinate], color: Color) -> Result<Vec<Point>, ShapeError> {
+    validate_segments(vertices.len(), 2)?;
+    let mut points = vec![lit(vertices[0], color)];
+    for (index, from) in vertices.iter().enumerate() {
+        let to = vertices[(index + 1) % vertices.len()];
+        push_edge(&mut points, *from, to, color);
+    }
+
+    Ok(with_leads(points))
+}
+
+/// Amount of segments is outside of the supported range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeError {
+    /// Not enough segments to draw a closed shape
+    TooFewSegments(usize),
+    /// More segments than [MAX_SEGMENTS]
+    TooManySegments(usize),
+}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ShapeError::TooFewSegments(count) => write!(f, "shape with {} segments is not closed", count),
+            ShapeError::TooManySegments(count) => write!(f, "shape has {} segments, max is {}", count, MAX_SEGMENTS),
+        }
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+fn validate_segments(segments: usize, min: usize) -> Result<(), ShapeError> {
+    if segments < min {
+        return Err(ShapeError::TooFewSegments(segments));
+    }
+    if segments > MAX_SEGMENTS {
+        return Err(ShapeError::TooManySegments(segments));
+    }
+
+    Ok(())
+}
+
+/// Appends the points from `from` (exclusive) to `to` (inclusive)
+fn push_edge(points: &mut Vec<Point>, from: Coordinate, to: Coordinate, color: Color) {
+    let dx = f32::from(to.x) - f32::from(from.x);
+    let dy = f32::from(to.y) - f32::from(from.y);
+    let steps = (dx.hypot(dy) / f32::from(LINE_STEP)).ceil().max(1.0) as usize;
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        let coordinate = Coordinate {
+            x: clamp(f32::from(from.x) + dx * t),
+            y: clamp(f32::from(from.y) + dy * t),
+        };
+        points.push(lit(coordinate, color));
+    }
+}
+
+fn with_leads(points: Vec<Point>) -> Vec<Point> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (first.blanked(), last.blanked()),
+        _ => return points,
+    };
+    let mut result = Vec::with_capacity(points.len() + 2 * LEAD_POINTS);
+    result.extend(std::iter::repeat_n(first, LEAD_POINTS));
+    result.extend(points);
+    result.extend(std::iter::repeat_n(last, LEAD_POINTS));
+
+    result
+}
+
+fn lit(coordinate: Coordinate, color: Color) -> Point {
+    Point {
+        coordinate,
+        color,
+        intensity: 0xFF,
+    }
+}
+
+fn clamp(value: f32) -> u16 {
+    value.round().clamp(0.0, f32::from(Coordinate::MAX)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255 };
+
+    fn at(x: u16, y: u16) -> Coordinate {
+        Coordinate { x, y }
+    }
+
+    /// Checks the blanked lead-in and lead-out and returns the lit points in between
+    fn lit_part(points: &[Point]) -> &[Point] {
+        let lit = &points[LEAD_POINTS..points.len() - LEAD_POINTS];
+        for lead in &points[..LEAD_POINTS] {
+            assert!(lead.is_blanked());
+            assert_eq!(lead.coordinate, lit[0].coordinate);
+        }
+        for lead in &points[points.len() - LEAD_POINTS..] {
+            assert!(lead.is_blanked());
+            assert_eq!(lead.coordinate, lit[lit.len() - 1].coordinate);
+        }
+        assert!(lit.iter().all(|point| !point.is_blanked()));
+
+        lit
+    }
+
+    fn distance(a: Coordinate, b: Coordinate) -> f32 {
+        (f32::from(a.x) - f32::from(b.x)).hypot(f32::from(a.y) - f32::from(b.y))
+    }
+
+    #[test]
+    fn circles_are_closed_and_keep_their_radius() {
+        let center = at(2000, 2000);
+        let points = circle(center, 1000, 36, WHITE).unwrap();
+
+        assert_eq!(points.len(), 36 + 1 + 2 * LEAD_POINTS);
+        let lit = lit_part(&points);
+        assert_eq!(lit[0].coordinate, lit[36].coordinate);
+        for point in lit {
+            assert!((distance(point.coordinate, center) - 1000.0).abs() <= 1.0);
+            assert_eq!(point.color, WHITE);
+        }
+    }
+
+    #[test]
+    fn circles_are_clamped_to_the_coordinate_space() {
+        let points = circle(at(0, Coordinate::MAX), 500, 16, WHITE).unwrap();
+
+        let max = Coordinate::MAX;
+        assert!(points.iter().all(|point| point.coordinate.x <= max && point.coordinate.y <= max));
+        assert!(points.iter().any(|point| point.coordinate.x == 0));
+        assert!(points.iter().any(|point| point.coordinate.y == Coordinate::MAX));
+    }
+
+    #[test]
+    fn segment_counts_are_validated() {
+        assert_eq!(circle(at(0, 0), 10, 2, WHITE), Err(ShapeError::TooFewSegments(2)));
+        assert_eq!(circle(at(0, 0), 10, MAX_SEGMENTS + 1, WHITE), Err(ShapeError::TooManySegments(MAX_SEGMENTS + 1)));
+        assert!(circle(at(0, 0), 10, 3, WHITE).is_ok());
+        assert!(circle(at(0, 0), 10, MAX_SEGMENTS, WHITE).is_ok());
+
+        assert_eq!(polygon(&[at(0, 0)], WHITE), Err(ShapeError::TooFewSegments(1)));
+        let vertices = vec![at(0, 0); MAX_SEGMENTS + 1];
+        assert_eq!(polygon(&vertices, WHITE), Err(ShapeError::TooManySegments(MAX_SEGMENTS + 1)));
+        assert!(polygon(&[at(0, 0), at(100, 0)], WHITE).is_ok());
+    }
+
+    #[test]
+    fn lines_run_from_start_to_end_in_short_steps() {
+        let (a, b) = (at(100, 4000), at(3900, 50));
+        let points = line(a, b, WHITE);
+
+        let lit = lit_part(&points);
+        assert_eq!(lit[0].coordinate, a);
+        assert_eq!(lit[lit.len() - 1].coordinate, b);
+        for pair in lit.windows(2) {
+            assert!(distance(pair[0].coordinate, pair[1].coordinate) <= f32::from(LINE_STEP) + 1.0);
+        }
+    }
+
+    #[test]
+    fn lines_of_zero_length_still_draw_a_point() {
+        let points = line(at(500, 500), at(500, 500), WHITE);
+
+        let lit = lit_part(&points);
+        assert!(!lit.is_empty());
+        assert!(lit.iter().all(|point| point.coordinate == at(500, 500)));
+    }
+
+    #[test]
+    fn polygons_pass_every_vertex_and_close() {
+        let vertices = [at(1000, 1000), at(3000, 1000), at(2000, 3000)];
+        let points = polygon(&vertices, WHITE).unwrap();
+
+        let lit = lit_part(&points);
+        assert_eq!(lit[0].coordinate, vertices[0]);
+        assert_eq!(lit[lit.len() - 1].coordinate, vertices[0]);
+        for vertex in &vertices {
+            assert!(lit.iter().any(|point| point.coordinate == *vertex));
+        }
+        for pair in lit.windows(2) {
+            assert!(distance(pair[0].coordinate, pair[1].coordinate) <= f32::from(LINE_STEP) + 1.0);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+//! Checks for whole shows before they are sent to a dac
+
+use crate::frame::{Coordinate, Frame, Point};
+use crate::safety::SafetyLimiter;
+
+/// Checks all frames of a show drawn at `fps` against the limits of the dac and returns every issue found
+///
+/// Frames without points are skipped as they only stop the output.
+pub fn validate_show(frames: &[Vec<Point>], fps: f32) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    for (frame, points) in frames.iter().enumerate() {
+        if points.is_empty() {
+            continue;
+        }
+        if points.len() > Frame::MAX_POINTS {
+            issues.push(ValidationIssue::TooManyPoints {
+                frame,
+                points: points.len(),
+            });
+        }
+        let pps = (points.len() as f32 * fps).round() as u32;
+        if pps > Frame::MAX_PPS {
+            issues.push(ValidationIssue::PpsTooHigh { frame, pps });
+        } else if pps < Frame::MIN_PPS {
+            issues.push(ValidationIssue::PpsTooLow { frame, pps });
+        }
+        for (point, value) in points.iter().enumerate() {
+            if value.coordinate.x > Coordinate::MAX || value.coordinate.y > Coordinate::MAX {
+                issues.push(ValidationIssue::CoordinateOutOfRange {
+                    frame,
+                    point,
+                    coordinate: value.coordinate,
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Same as [validate_show] but also reports frames rejected by the safety `limiter`
+pub fn validate_show_with_limiter(frames: &[Vec<Point>], fps: f32, limiter: &SafetyLimiter) -> Vec<ValidationIssue> {
+    let mut issues = validate_show(frames, fps);
+    for (frame, points) in frames.iter().enumerate() {
+        if let Err(err) = limiter.scale_for(points) {
+            issues.push(ValidationIssue::SafetyLimitExceeded {
+                frame,
+                point: err.point,
+                power: err.power,
+            });
+        }
+    }
+    issues.sort_by_key(ValidationIssue::frame);
+
+    issues
+}
+
+/// Problem found by [validate_show]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValidationIssue {
+    /// Frame contains more than [Frame::MAX_POINTS] points
+    TooManyPoints { frame: usize, points: usize },
+    /// Drawing the frame at the frame rate needs a higher rate of output than [Frame::MAX_PPS]
+    PpsTooHigh { frame: usize, pps: u32 },
+    /// Drawing the frame at the frame rate needs a lower rate of output than [Frame::MIN_PPS]
+    PpsTooLow { frame: usize, pps: u32 },
+    /// Point is outside of the coordinate space, see [Coordinate::MAX]
+    CoordinateOutOfRange {
+        frame: usize,
+        point: usize,
+        coordinate: Coordinate,
+    },
+    /// Point exceeds the per point limit of the safety limiter even after scaling
+    SafetyLimitExceeded { frame: usize, point: usize, power: f32 },
+}
+
+impl ValidationIssue {
+    /// Index of the frame the issue was found in
+    pub fn frame(&self) -> usize {
+        match self {
+            ValidationIssue::TooManyPoints { frame, .. }
+            | ValidationIssue::PpsTooHigh { frame, .. }
+            | ValidationIssue::PpsTooLow { frame, .. }
+            | ValidationIssue::CoordinateOutOfRange { frame, .. }
+            | ValidationIssue::SafetyLimitExceeded { frame, .. } => *frame,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValidationIssue::TooManyPoints { frame, points } => {
+                write!(f, "frame {} contains {} points, max is {}", frame, points, Frame::MAX_POINTS)
+            }
+            ValidationIssue::PpsTooHigh { frame, pps } => {
+                write!(f, "frame {} needs {} pps, max is {}", frame, pps, Frame::MAX_PPS)
+            }
+            ValidationIssue::PpsTooLow { frame, pps } => {
+                write!(f, "frame {} needs {} pps, min is {}", frame, pps, Frame::MIN_PPS)
+            }
+            ValidationIssue::CoordinateOutOfRange { frame, point, coordinate } => write!(
+                f,
+                "point {} of frame {} at ({}, {}) is outside of the coordinate space",
+                point, frame, coordinate.x, coordinate.y
+            ),
+            ValidationIssue::SafetyLimitExceeded { frame, point, power } => {
+                write!(f, "point {} of frame {} has a power of {:.2} after scaling", point, frame, power)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(count: usize) -> Vec<Point> {
+        vec![Point::builder().position(0x800, 0x800).build(); count]
+    }
+
+    fn out_of_range(x: u16, y: u16) -> Point {
+        Point {
+            coordinate: Coordinate { x, y },
+            ..points(1)[0]
+        }
+    }
+
+    #[test]
+    fn valid_shows_have_no_issues() {
+        assert!(validate_show(&[points(1000), Vec::new(), points(Frame::MAX_POINTS / 2)], 30.0).is_empty());
+    }
+
+    #[test]
+    fn every_issue_of_every_frame_is_reported() {
+        let mut outside = points(100);
+        outside[3] = out_of_range(Coordinate::MAX + 1, 0);
+        outside[7] = out_of_range(0, u16::MAX);
+        let frames = vec![points(1000), points(Frame::MAX_POINTS + 1), outside, Vec::new(), points(3000)];
+
+        assert_eq!(validate_show(&frames, 30.0), vec![
+            ValidationIssue::TooManyPoints { frame: 1, points: Frame::MAX_POINTS + 1 },
+            ValidationIssue::PpsTooHigh { frame: 1, pps: 122_910 },
+            ValidationIssue::CoordinateOutOfRange { frame: 2, point: 3, coordinate: Coordinate { x: 0x1000, y: 0 } },
+            ValidationIssue::CoordinateOutOfRange { frame: 2, point: 7, coordinate: Coordinate { x: 0, y: u16::MAX } },
+            ValidationIssue::PpsTooHigh { frame: 4, pps: 90_000 },
+        ]);
+    }
+
+    #[test]
+    fn frames_drawn_too_slowly_are_reported() {
+        assert_eq!(validate_show(&[points(1)], 5.0), vec![ValidationIssue::PpsTooLow { frame: 0, pps: 5 }]);
+    }
+
+    #[test]
+    fn safety_issues_are_sorted_in_by_frame() {
+        let dark = vec![Point::builder().position(0, 0).intensity(0).build(); 10];
+        let mut bright = dark.clone();
+        bright[4] = Point::builder().position(0, 0).rgb(255, 255, 255).build();
+        let mut outside = dark.clone();
+        outside[2] = out_of_range(0x1000, 0x1000).blanked();
+        let limiter = SafetyLimiter::new(1.0).with_max_point_power(0.5);
+
+        let issues = validate_show_with_limiter(&[bright, outside, dark], 30.0, &limiter);
+
+        assert_eq!(issues.len(), 2);
+        assert!(matches!(issues[0], ValidationIssue::SafetyLimitExceeded { frame: 0, point: 4, .. }));
+        assert!(matches!(issues[1], ValidationIssue::CoordinateOutOfRange { frame: 1, point: 2, .. }));
+    }
+
+    #[test]
+    fn issues_describe_where_they_are() {
+        let issue = ValidationIssue::CoordinateOutOfRange {
+            frame: 2,
+            point: 7,
+            coordinate: Coordinate { x: 0, y: 5000 },
+        };
+
+        assert_eq!(issue.to_string(), "point 7 of frame 2 at (0, 5000) is outside of the coordinate space");
+    }
+}
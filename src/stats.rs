@@ -0,0 +1,49 @@
+//! Throughput accounting of written frames
+
+use std::time::{Duration, Instant};
+
+/// Weight of the newest frame in the rolling averages
+const SMOOTHING: f64 = 0.1;
+
+/// Frames and points written since the stats were last reset
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StreamStats {
+    /// Amount of frames written
+    pub frames: u64,
+    /// Amount of points written
+    pub points: u64,
+    /// Rolling average of the frames written per second
+    pub fps: f64,
+    /// Rolling average of the points written per second
+    pub pps: f64,
+    last_write: Option<Instant>,
+    average_interval: f64,
+    average_points: f64,
+}
+
+impl StreamStats {
+    /// Time of the last written frame
+    pub fn last_write(&self) -> Option<Instant> {
+        self.last_write
+    }
+
+    /// Accounts for a frame of `points` points written at `now`
+    pub(crate) fn record(&mut self, points: usize, now: Instant) {
+        self.frames += 1;
+        self.points += points as u64;
+        let points = points as f64;
+        if let Some(last_write) = self.last_write {
+            let interval = now.saturating_duration_since(last_write).max(Duration::from_micros(1)).as_secs_f64();
+            if self.frames == 2 {
+                self.average_interval = interval;
+                self.average_points = points;
+            } else {
+                self.average_interval += (interval - self.average_interval) * SMOOTHING;
+                self.average_points += (points - self.average_points) * SMOOTHING;
+            }
+            self.fps = 1.0 / self.average_interval;
+            self.pps = self.average_points / self.average_interval;
+        }
+        self.last_write = Some(now);
+    }
+}
@@ -0,0 +1,295 @@
+//! Rendering of text with a built-in single stroke vector font
+//!
+//! Glyphs are drawn on a grid 4 units wide and 6 units high with the baseline at 0.
+//! Lowercase letters are drawn as uppercase, characters without a glyph as `?`.
+
+use crate::frame::{Color, Coordinate, Point};
+
+/// Horizontal distance between the origins of two glyphs in font units
+const ADVANCE: f32 = 6.0;
+/// Height of capital letters in font units
+const CAP_HEIGHT: f32 = 6.0;
+/// Maximum distance between two points of a stroke in coordinate units
+const STEP: f32 = 48.0;
+/// Blanked points added at both ends of each movement between strokes
+const TRAVEL_POINTS: usize = 4;
+/// Lit points drawn at the position of a dot
+const DOT_POINTS: usize = 6;
+
+type Stroke = &'static [(i8, i8)];
+
+/// Renders a single line of text centered in the coordinate space
+///
+/// `scale` is the size of a font unit in coordinate units, so capitals are `6 * scale` high.
+/// Parts outside of the coordinate space are left out.
+pub fn render(text: &str, scale: f32, color: [u8; 3]) -> Vec<Point> {
+    let center = f32::from(Coordinate::MAX) / 2.0;
+    let x = center - width(text, scale) / 2.0;
+    let y = center - CAP_HEIGHT * scale / 2.0;
+
+    render_at(text, scale, color, x, y)
+}
+
+/// Renders frames of the text scrolling from the right edge of the coordinate space to the left edge
+///
+/// Each frame moves the text by `step` coordinate units, the text is vertically centered.
+/// In the first frame the text starts at the right edge, the last frame is the first one after it left on the left.
+pub fn render_scroll(text: &str, scale: f32, color: [u8; 3], step: f32) -> Vec<Vec<Point>> {
+    let center = f32::from(Coordinate::MAX) / 2.0;
+    let y = center - CAP_HEIGHT * scale / 2.0;
+    let distance = f32::from(Coordinate::MAX) + width(text, scale);
+    let frames = (distance / step.max(1.0)).ceil() as usize + 1;
+
+    (0..frames)
+        .map(|frame| {
+            let x = f32::from(Coordinate::MAX) - frame as f32 * step.max(1.0);
+            render_at(text, scale, color, x, y)
+        })
+        .collect()
+}
+
+/// Width of the rendered text in coordinate units
+pub fn width(text: &str, scale: f32) -> f32 {
+    let glyphs = text.chars().count();
+    if glyphs == 0 {
+        return 0.0;
+    }
+
+    ((glyphs - 1) as f32 * ADVANCE + 4.0) * scale
+}
+
+/// Renders the text with the bottom left corner of its first glyph at (x, y)
+fn render_at(text: &str, scale: f32, [r, g, b]: [u8; 3], x: f32, y: f32) -> Vec<Point> {
+    let color = Color::new(r, g, b);
+    let mut points = Vec::new();
+    let mut last: Option<Point> = None;
+    for (index, character) in text.chars().enumerate() {
+        let origin = x + index as f32 * ADVANCE * scale;
+        for stroke in glyph(character) {
+            let positions = stroke
+                .iter()
+                .map(|(gx, gy)| (origin + f32::from(*gx) * scale, y + f32::from(*gy) * scale))
+                .collect::<Vec<_>>();
+            draw_stroke(&mut points, &mut last, &positions, color);
+        }
+    }
+    if let Some(last) = last {
+        points.extend(std::iter::repeat_n(last.blanked(), TRAVEL_POINTS));
+    }
+
+    points
+}
+
+/// Samples the stroke, moves there with the laser off first and leaves out points outside of the coordinate space
+fn draw_stroke(points: &mut Vec<Point>, last: &mut Option<Point>, positions: &[(f32, f32)], color: Color) {
+    let mut samples = Vec::new();
+    if let [(x, y)] = positions {
+        samples.extend(std::iter::repeat_n((*x, *y), DOT_POINTS));
+    }
+    for segment in positions.windows(2) {
+        let ((x0, y0), (x1, y1)) = (segment[0], segment[1]);
+        let steps = ((x1 - x0).hypot(y1 - y0) / STEP).ceil().max(1.0) as usize;
+        if samples.is_empty() {
+            samples.push((x0, y0));
+        }
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            samples.push((x0 + (x1 - x0) * t, y0 + (y1 - y0) * t));
+        }
+    }
+
+    let mut gap = true;
+    for (x, y) in samples {
+        let coordinate = match to_coordinate(x, y) {
+            Some(coordinate) => coordinate,
+            None => {
+                gap = true;
+                continue;
+            }
+        };
+        let point = Point {
+            coordinate,
+            color,
+            intensity: 0xFF,
+        };
+        if gap {
+            if let Some(last) = last {
+                points.extend(std::iter::repeat_n(last.blanked(), TRAVEL_POINTS));
+            }
+            points.extend(std::iter::repeat_n(point.blanked(), TRAVEL_POINTS));
+            gap = false;
+        }
+        points.push(point);
+        *last = Some(point);
+    }
+}
+
+fn to_coordinate(x: f32, y: f32) -> Option<Coordinate> {
+    let range = 0.0..=f32::from(Coordinate::MAX);
+    if !range.contains(&x.round()) || !range.contains(&y.round()) {
+        return None;
+    }
+
+    Some(Coordinate {
+        x: x.round() as u16,
+        y: y.round() as u16,
+    })
+}
+
+/// Strokes of a glyph, a stroke with a single position is drawn as a dot
+fn glyph(character: char) -> &'static [Stroke] {
+    const O: Stroke = &[(1, 0), (0, 1), (0, 5), (1, 6), (3, 6), (4, 5), (4, 1), (3, 0), (1, 0)];
+    const P: Stroke = &[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)];
+
+    match character.to_ascii_uppercase() {
+        ' ' => &[],
+        'A' => &[&[(0, 0), (0, 4), (2, 6), (4, 4), (4, 0)], &[(0, 3), (4, 3)]],
+        'B' => &[&[(0, 0), (0, 6), (3, 6), (4, 5), (4, 4), (3, 3), (0, 3)], &[(3, 3), (4, 2), (4, 1), (3, 0), (0, 0)]],
+        'C' => &[&[(4, 5), (3, 6), (1, 6), (0, 5), (0, 1), (1, 0), (3, 0), (4, 1)]],
+        'D' => &[&[(0, 0), (0, 6), (2, 6), (4, 4), (4, 2), (2, 0), (0, 0)]],
+        'E' => &[&[(4, 6), (0, 6), (0, 0), (4, 0)], &[(0, 3), (3, 3)]],
+        'F' => &[&[(4, 6), (0, 6), (0, 0)], &[(0, 3), (3, 3)]],
+        'G' => &[&[(4, 5), (3, 6), (1, 6), (0, 5), (0, 1), (1, 0), (3, 0), (4, 1), (4, 3), (2, 3)]],
+        'H' => &[&[(0, 0), (0, 6)], &[(4, 0), (4, 6)], &[(0, 3), (4, 3)]],
+        'I' => &[&[(1, 6), (3, 6)], &[(2, 6), (2, 0)], &[(1, 0), (3, 0)]],
+        'J' => &[&[(4, 6), (4, 1), (3, 0), (1, 0), (0, 1)]],
+        'K' => &[&[(0, 0), (0, 6)], &[(4, 6), (0, 2)], &[(1, 3), (4, 0)]],
+        'L' => &[&[(0, 6), (0, 0), (4, 0)]],
+        'M' => &[&[(0, 0), (0, 6), (2, 3), (4, 6), (4, 0)]],
+        'N' => &[&[(0, 0), (0, 6), (4, 0), (4, 6)]],
+        'O' => &[O],
+        'P' => &[P],
+        'Q' => &[O, &[(2, 2), (4, 0)]],
+        'R' => &[P, &[(2, 3), (4, 0)]],
+        'S' => &[&[(4, 5), (3, 6), (1, 6), (0, 5), (0, 4), (1, 3), (3, 3), (4, 2), (4, 1), (3, 0), (1, 0), (0, 1)]],
+        'T' => &[&[(0, 6), (4, 6)], &[(2, 6), (2, 0)]],
+        'U' => &[&[(0, 6), (0, 1), (1, 0), (3, 0), (4, 1), (4, 6)]],
+        'V' => &[&[(0, 6), (2, 0), (4, 6)]],
+        'W' => &[&[(0, 6), (1, 0), (2, 3), (3, 0), (4, 6)]],
+        'X' => &[&[(0, 0), (4, 6)], &[(0, 6), (4, 0)]],
+        'Y' => &[&[(0, 6), (2, 3), (4, 6)], &[(2, 3), (2, 0)]],
+        'Z' => &[&[(0, 6), (4, 6), (0, 0), (4, 0)]],
+        '0' => &[O, &[(0, 1), (4, 5)]],
+        '1' => &[&[(1, 5), (2, 6), (2, 0)], &[(1, 0), (3, 0)]],
+        '2' => &[&[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (0, 0), (4, 0)]],
+        '3' => &[&[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (3, 3), (4, 2), (4, 1), (3, 0), (1, 0), (0, 1)], &[(1, 3), (3, 3)]],
+        '4' => &[&[(3, 0), (3, 6), (0, 2), (4, 2)]],
+        '5' => &[&[(4, 6), (0, 6), (0, 3), (3, 3), (4, 2), (4, 1), (3, 0), (0, 0)]],
+        '6' => &[&[(4, 5), (3, 6), (1, 6), (0, 5), (0, 1), (1, 0), (3, 0), (4, 1), (4, 2), (3, 3), (0, 3)]],
+        '7' => &[&[(0, 6), (4, 6), (1, 0)]],
+        '8' => &[&[
+            (1, 3), (0, 4), (0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (3, 3),
+            (1, 3), (0, 2), (0, 1), (1, 0), (3, 0), (4, 1), (4, 2), (3, 3),
+        ]],
+        '9' => &[&[(4, 3), (1, 3), (0, 4), (0, 5), (1, 6), (3, 6), (4, 5), (4, 1), (3, 0), (1, 0), (0, 1)]],
+        '.' => &[&[(2, 0)]],
+        ',' => &[&[(2, 1), (1, -1)]],
+        ':' => &[&[(2, 4)], &[(2, 1)]],
+        '!' => &[&[(2, 6), (2, 2)], &[(2, 0)]],
+        '\'' => &[&[(2, 6), (2, 4)]],
+        '-' => &[&[(1, 3), (3, 3)]],
+        '+' => &[&[(0, 3), (4, 3)], &[(2, 1), (2, 5)]],
+        '=' => &[&[(0, 2), (4, 2)], &[(0, 4), (4, 4)]],
+        '/' => &[&[(0, 0), (4, 6)]],
+        _ => &[&[(0, 5), (1, 6), (3, 6), (4, 5), (4, 4), (2, 3), (2, 2)], &[(2, 0)]],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [u8; 3] = [255, 255, 255];
+
+    fn lit(points: &[Point]) -> impl Iterator<Item = &Point> + Clone {
+        points.iter().filter(|point| !point.is_blanked())
+    }
+
+    /// Smallest and largest lit coordinates on both axes
+    fn bounds(points: &[Point]) -> ((u16, u16), (u16, u16)) {
+        let xs = lit(points).map(|point| point.coordinate.x);
+        let ys = lit(points).map(|point| point.coordinate.y);
+
+        ((xs.clone().min().unwrap(), xs.max().unwrap()), (ys.clone().min().unwrap(), ys.max().unwrap()))
+    }
+
+    #[test]
+    fn text_is_centered_in_the_coordinate_space() {
+        let points = render("HELLO", 40.0, WHITE);
+
+        let ((left, right), (bottom, top)) = bounds(&points);
+        assert_eq!(f32::from(right - left), width("HELLO", 40.0));
+        assert_eq!(f32::from(top - bottom), CAP_HEIGHT * 40.0);
+        let center = f32::from(Coordinate::MAX) / 2.0;
+        assert!((f32::from(left + right) / 2.0 - center).abs() <= 1.0);
+        assert!((f32::from(bottom + top) / 2.0 - center).abs() <= 1.0);
+    }
+
+    #[test]
+    fn glyphs_advance_by_a_fixed_distance() {
+        assert_eq!(width("", 10.0), 0.0);
+        assert_eq!(width("A", 10.0), 40.0);
+        assert_eq!(width("AB", 10.0), 100.0);
+    }
+
+    #[test]
+    fn movements_between_strokes_are_blanked() {
+        let points = render("A.B", 50.0, WHITE);
+
+        assert!(points[0].is_blanked());
+        assert!(points[points.len() - 1].is_blanked());
+        for pair in points.windows(2) {
+            if !pair[0].is_blanked() && !pair[1].is_blanked() {
+                let (a, b) = (pair[0].coordinate, pair[1].coordinate);
+                let distance = (f32::from(a.x) - f32::from(b.x)).hypot(f32::from(a.y) - f32::from(b.y));
+                assert!(distance <= STEP + 1.0, "lit jump of {} from {:?} to {:?}", distance, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn dots_are_drawn_as_repeated_points() {
+        let points = render(".", 50.0, WHITE);
+
+        assert_eq!(lit(&points).count(), DOT_POINTS);
+        let first = lit(&points).next().unwrap().coordinate;
+        assert!(lit(&points).all(|point| point.coordinate == first));
+    }
+
+    #[test]
+    fn lowercase_and_unknown_characters_are_substituted() {
+        assert_eq!(render("abc", 30.0, WHITE), render("ABC", 30.0, WHITE));
+        assert_eq!(render("~", 30.0, WHITE), render("\u{e9}", 30.0, WHITE));
+        assert!(render("   ", 30.0, WHITE).is_empty());
+        assert!(render("", 30.0, WHITE).is_empty());
+    }
+
+    #[test]
+    fn parts_outside_of_the_coordinate_space_are_left_out() {
+        let points = render("WIDE TEXT", 200.0, WHITE);
+
+        assert!(!points.is_empty());
+        // the strokes are cut where they leave the coordinate space, up to one step away from its edges
+        let ((left, right), _) = bounds(&points);
+        assert!(f32::from(left) <= STEP);
+        assert!(f32::from(right) >= f32::from(Coordinate::MAX) - STEP);
+        assert!(points.iter().all(|point| point.coordinate.y <= Coordinate::MAX));
+    }
+
+    #[test]
+    fn scrolling_text_moves_from_the_right_edge_to_the_left() {
+        let frames = render_scroll("HI", 20.0, WHITE, 100.0);
+
+        let distance = f32::from(Coordinate::MAX) + width("HI", 20.0);
+        assert_eq!(frames.len(), (distance / 100.0).ceil() as usize + 1);
+        assert!(lit(&frames[0]).all(|point| point.coordinate.x == Coordinate::MAX));
+        assert!(frames[frames.len() - 1].is_empty());
+
+        let ((left, _), _) = bounds(&frames[20]);
+        let ((next_left, _), _) = bounds(&frames[21]);
+        assert_eq!(left - next_left, 100);
+        for frame in &frames[1..frames.len() - 1] {
+            assert!(lit(frame).count() > 0);
+        }
+    }
+}
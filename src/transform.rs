@@ -0,0 +1,242 @@
+//! Geometric transforms operating in place on the 12 bit coordinate space.
+//!
+//! Scaling and rotation happen around the center of the coordinate space.
+//! Results outside of the coordinate space are clamped to its edges instead of wrapping around.
+
+use crate::frame::{Coordinate, Point};
+
+const CENTER: f32 = Coordinate::MAX as f32 / 2.0;
+
+/// Moves all points by (dx, dy)
+pub fn translate(points: &mut [Point], dx: i16, dy: i16) {
+    for point in points {
+        point.coordinate.x = offset(point.coordinate.x, dx);
+        point.coordinate.y = offset(point.coordinate.y, dy);
+    }
+}
+
+/// Scales all points by (sx, sy)
+pub fn scale(points: &mut [Point], sx: f32, sy: f32) {
+    transform_with_matrix(points, [[sx, 0.0], [0.0, sy]], [0, 0]);
+}
+
+/// Rotates all points counter clockwise
+pub fn rotate(points: &mut [Point], radians: f32) {
+    let (sin, cos) = radians.sin_cos();
+    transform_with_matrix(points, [[cos, -sin], [sin, cos]], [0, 0]);
+}
+
+/// Applies `matrix * point + translation` to all points in a single pass
+pub fn transform_with_matrix(points: &mut [Point], matrix: [[f32; 2]; 2], translation: [i16; 2]) {
+    for point in points {
+        let x = f32::from(point.coordinate.x) - CENTER;
+        let y = f32::from(point.coordinate.y) - CENTER;
+
+        let transformed_x = matrix[0][0] * x + matrix[0][1] * y + CENTER + f32::from(translation[0]);
+        let transformed_y = matrix[1][0] * x + matrix[1][1] * y + CENTER + f32::from(translation[1]);
+
+        point.coordinate.x = clamp(transformed_x);
+        point.coordinate.y = clamp(transformed_y);
+    }
+}
+
+/// Mirrors all points along the vertical center line
+///
+/// A coordinate `x` becomes `Coordinate::MAX - x`, so 0 and 0xFFF swap places.
+/// The coordinate space has an even amount of values so no value maps onto itself and flipping twice restores the input.
+/// Coordinates above [Coordinate::MAX] are clamped first.
+pub fn flip_x(points: &mut [Point]) {
+    for point in points {
+        point.coordinate.x = flip(point.coordinate.x);
+    }
+}
+
+/// Mirrors all points along the horizontal center line, see [flip_x]
+pub fn flip_y(points: &mut [Point]) {
+    for point in points {
+        point.coordinate.y = flip(point.coordinate.y);
+    }
+}
+
+/// Mirrors all points along both axes, see [flip_x]
+pub fn flip_xy(points: &mut [Point]) {
+    for point in points {
+        point.coordinate.x = flip(point.coordinate.x);
+        point.coordinate.y = flip(point.coordinate.y);
+    }
+}
+
+/// Coordinate convention of an authoring tool, which values are at the edges of the coordinate space
+///
+/// Axis directions follow from the order of the edges, e.g. a `top` smaller than `bottom` means y grows downwards.
+/// The dac itself uses 0 to [Coordinate::MAX] with the origin in the bottom left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateSpace {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl CoordinateSpace {
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        CoordinateSpace { left, right, bottom, top }
+    }
+
+    /// Space from -1.0 to 1.0 on both axes with the origin in the center and y growing upwards
+    pub fn normalized() -> Self {
+        CoordinateSpace::new(-1.0, 1.0, -1.0, 1.0)
+    }
+
+    /// Space of a screen or image with the origin in the top left corner and y growing downwards
+    pub fn screen(width: f32, height: f32) -> Self {
+        CoordinateSpace::new(0.0, width, height, 0.0)
+    }
+
+    /// Converts a position in this space into the coordinate space of the dac
+    ///
+    /// Positions outside of this space are clamped to its edges.
+    pub fn to_coordinate(&self, x: f32, y: f32) -> Coordinate {
+        Coordinate {
+            x: to_dac(x, self.left, self.right),
+            y: to_dac(y, self.bottom, self.top),
+        }
+    }
+
+    /// Converts a coordinate of the dac into a position in this space, inverse of [to_coordinate](CoordinateSpace::to_coordinate)
+    pub fn to_position(&self, coordinate: Coordinate) -> (f32, f32) {
+        (
+            from_dac(coordinate.x, self.left, self.right),
+            from_dac(coordinate.y, self.bottom, self.top),
+        )
+    }
+
+    /// Converts the coordinates of all points, which are read as positions in this space
+    ///
+    /// Only spaces without negative values fit into the coordinates of a [Point],
+    /// use [to_coordinate](CoordinateSpace::to_coordinate) for the others.
+    pub fn remap(&self, points: &mut [Point]) {
+        for point in points {
+            point.coordinate = self.to_coordinate(f32::from(point.coordinate.x), f32::from(point.coordinate.y));
+        }
+    }
+}
+
+/// The coordinate space of the dac, which leaves coordinates as they are
+impl Default for CoordinateSpace {
+    fn default() -> Self {
+        CoordinateSpace::new(0.0, f32::from(Coordinate::MAX), 0.0, f32::from(Coordinate::MAX))
+    }
+}
+
+fn to_dac(value: f32, start: f32, end: f32) -> u16 {
+    if start == end {
+        return clamp(CENTER);
+    }
+
+    clamp((value - start) / (end - start) * f32::from(Coordinate::MAX))
+}
+
+fn from_dac(value: u16, start: f32, end: f32) -> f32 {
+    start + f32::from(value.min(Coordinate::MAX)) / f32::from(Coordinate::MAX) * (end - start)
+}
+
+fn flip(value: u16) -> u16 {
+    Coordinate::MAX - value.min(Coordinate::MAX)
+}
+
+fn offset(value: u16, delta: i16) -> u16 {
+    (i32::from(value) + i32::from(delta)).clamp(0, i32::from(Coordinate::MAX)) as u16
+}
+
+fn clamp(value: f32) -> u16 {
+    value.round().clamp(0.0, f32::from(Coordinate::MAX)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDGES: [u16; 4] = [0, 0x7FF, 0x800, Coordinate::MAX];
+
+    fn points() -> Vec<Point> {
+        EDGES
+            .iter()
+            .flat_map(|x| EDGES.iter().map(move |y| Point::builder().position(*x, *y).build()))
+            .collect()
+    }
+
+    #[test]
+    fn flips_map_edges_onto_each_other() {
+        let mut points = [
+            Point::builder().position(0, 0x7FF).build(),
+            Point::builder().position(0x800, Coordinate::MAX).build(),
+        ];
+        flip_x(&mut points);
+
+        assert_eq!(points[0].coordinate, Coordinate { x: Coordinate::MAX, y: 0x7FF });
+        assert_eq!(points[1].coordinate, Coordinate { x: 0x7FF, y: Coordinate::MAX });
+    }
+
+    #[test]
+    fn flipping_twice_restores_the_points() {
+        for flip in [flip_x, flip_y, flip_xy].iter() {
+            let mut flipped = points();
+            flip(&mut flipped);
+            assert_ne!(flipped, points());
+            flip(&mut flipped);
+
+            assert_eq!(flipped, points());
+        }
+    }
+
+    #[test]
+    fn coordinate_spaces_round_trip_every_coordinate() {
+        let spaces = [
+            CoordinateSpace::default(),
+            CoordinateSpace::normalized(),
+            CoordinateSpace::screen(1920.0, 1080.0),
+            CoordinateSpace::new(100.0, -100.0, 5.0, -5.0),
+        ];
+        for space in spaces.iter() {
+            for value in 0..=Coordinate::MAX {
+                let coordinate = Coordinate { x: value, y: Coordinate::MAX - value };
+                let (x, y) = space.to_position(coordinate);
+
+                assert_eq!(space.to_coordinate(x, y), coordinate, "{:?} in {:?}", coordinate, space);
+            }
+        }
+    }
+
+    #[test]
+    fn coordinate_spaces_map_their_edges_onto_the_edges_of_the_dac() {
+        let normalized = CoordinateSpace::normalized();
+        assert_eq!(normalized.to_coordinate(-1.0, -1.0), Coordinate { x: 0, y: 0 });
+        assert_eq!(normalized.to_coordinate(1.0, 1.0), Coordinate { x: Coordinate::MAX, y: Coordinate::MAX });
+        assert_eq!(normalized.to_position(Coordinate { x: 0, y: Coordinate::MAX }), (-1.0, 1.0));
+
+        let screen = CoordinateSpace::screen(640.0, 480.0);
+        assert_eq!(screen.to_coordinate(0.0, 0.0), Coordinate { x: 0, y: Coordinate::MAX });
+        assert_eq!(screen.to_coordinate(640.0, 480.0), Coordinate { x: Coordinate::MAX, y: 0 });
+        // outside of the space is clamped to its edges
+        assert_eq!(screen.to_coordinate(-10.0, 1000.0), Coordinate { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn empty_coordinate_spaces_map_onto_the_center() {
+        let space = CoordinateSpace::new(1.0, 1.0, 1.0, 1.0);
+
+        assert_eq!(space.to_coordinate(1.0, 5.0), Coordinate { x: 0x800, y: 0x800 });
+    }
+
+    #[test]
+    fn flip_xy_flips_both_axes() {
+        let mut both = points();
+        flip_xy(&mut both);
+        let mut each = points();
+        flip_x(&mut each);
+        flip_y(&mut each);
+
+        assert_eq!(both, each);
+    }
+}
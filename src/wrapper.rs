@@ -39,7 +39,7 @@ impl HeliosDacWrapper {
         let mut points = frame.points.into_iter().map(HeliosPoint::from).collect::<Vec<_>>();
         let point_count = points.len() as u32;
         let point_ptr = points.as_mut_ptr();
-        let result = unsafe { self.dac.WriteFrame(device_number, frame.pps, 0, point_ptr, point_count) };
+        let result = unsafe { self.dac.WriteFrame(device_number, frame.pps, frame.flags.bits(), point_ptr, point_count) };
 
         parse_error(result)
     }
@@ -109,43 +109,87 @@ impl HeliosDacWrapper {
 }
 
 fn parse_error(code: i32) -> Result<(), HeliosError> {
-    if code == helios_dac_sys::HELIOS_SUCCESS as i32 {
-        Ok(())
-    }else {
-        unimplemented!("error parsing not done yet")
-    }
+    let error = match code {
+        code if code == HELIOS_SUCCESS as i32 => return Ok(()),
+        code if code == HELIOS_ERROR_NOT_INITIALIZED as i32 => HeliosError::NotInitialized,
+        code if code == HELIOS_ERROR_INVALID_DEVNUM as i32 => HeliosError::InvalidDevNum,
+        code if code == HELIOS_ERROR_NULL_POINTS as i32 => HeliosError::NullPoints,
+        code if code == HELIOS_ERROR_TOO_MANY_POINTS as i32 => HeliosError::TooManyPoints,
+        code if code == HELIOS_ERROR_PPS_TOO_HIGH as i32 => HeliosError::PPSTooHigh,
+        code if code == HELIOS_ERROR_PPS_TOO_LOW as i32 => HeliosError::PPSTooLow,
+        code if code == HELIOS_ERROR_DEVICE_CLOSED as i32 => HeliosError::DeviceClosed,
+        code if code == HELIOS_ERROR_DEVICE_FRAME_READY as i32 => HeliosError::DeviceFrameReady,
+        code if code == HELIOS_ERROR_DEVICE_SEND_CONTROL as i32 => HeliosError::DeviceSendControl,
+        code if code == HELIOS_ERROR_DEVICE_RESULT as i32 => HeliosError::DeviceResult,
+        code if code == HELIOS_ERROR_DEVICE_NULL_BUFFER as i32 => HeliosError::DeviceNullBuffer,
+        code if code == HELIOS_ERROR_DEVICE_SIGNAL_TOO_LONG as i32 => HeliosError::DeviceSignalTooLong,
+        // libusb error codes range from -1 to -99
+        code if (HELIOS_ERROR_LIBUSB_BASE as i32 - 99..HELIOS_ERROR_LIBUSB_BASE as i32).contains(&code) => {
+            HeliosError::Libusb(code - HELIOS_ERROR_LIBUSB_BASE as i32)
+        }
+        code => HeliosError::Unknown(code),
+    };
+
+    Err(error)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeliosError {
     /// Attempted to perform an action before calling OpenDevices()
-    NotInitialized = HELIOS_ERROR_NOT_INITIALIZED as isize,
+    NotInitialized,
     /// Attempted to perform an action with an invalid device number
-    InvalidDevNum = HELIOS_ERROR_INVALID_DEVNUM as isize,
+    InvalidDevNum,
     /// [write_frame](HeliosDacWrapper::write_frame) called with null pointer to points
-    NullPoints = HELIOS_ERROR_NULL_POINTS as isize,
+    NullPoints,
     /// [write_frame](HeliosDacWrapper::write_frame) called with a frame containing too many points
-    TooManyPoints = HELIOS_ERROR_TOO_MANY_POINTS as isize,
+    TooManyPoints,
     /// [write_frame](HeliosDacWrapper::write_frame) called with pps higher than maximum allowed
-    PPSTooHigh = HELIOS_ERROR_PPS_TOO_HIGH as isize,
+    PPSTooHigh,
     /// [write_frame](HeliosDacWrapper::write_frame) called with pps lower than minimum allowed
-    PPSTooLow = HELIOS_ERROR_PPS_TOO_LOW as isize,
+    PPSTooLow,
     /// Attempted to perform an operation on a closed DAC device
-    DeviceClosed = HELIOS_ERROR_DEVICE_CLOSED as isize,
+    DeviceClosed,
     /// Attempted to send a new frame with HELIOS_FLAGS_DONT_BLOCK before previous DoFrame() completed
-    DeviceFrameReady = HELIOS_ERROR_DEVICE_FRAME_READY as isize,
+    DeviceFrameReady,
     /// Operation failed because SendControl() failed (if operation failed because of libusb_interrupt_transfer failure, the error code will be a libusb error instead)
-    DeviceSendControl = HELIOS_ERROR_DEVICE_SEND_CONTROL as isize,
+    DeviceSendControl,
     /// Received an unexpected result from a call to SendControl()
-    DeviceResult = HELIOS_ERROR_DEVICE_RESULT as isize,
+    DeviceResult,
     /// Attempted to call SendControl() with a null buffer pointer
-    DeviceNullBuffer = HELIOS_ERROR_DEVICE_NULL_BUFFER as isize,
+    DeviceNullBuffer,
     /// Attempted to call SendControl() with a control signal that is too long
-    DeviceSignalTooLong = HELIOS_ERROR_DEVICE_SIGNAL_TOO_LONG as isize,
-    /// Errors from libusb are the libusb error code added to -5000.
-    Libusb = HELIOS_ERROR_LIBUSB_BASE as isize
+    DeviceSignalTooLong,
+    /// Errors from libusb are the libusb error code added to -5000, contains the libusb error code
+    Libusb(i32),
+    /// Code which isn't documented by the sdk
+    Unknown(i32),
+}
+
+impl std::fmt::Display for HeliosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let message = match self {
+            HeliosError::NotInitialized => "devices are not opened",
+            HeliosError::InvalidDevNum => "invalid device number",
+            HeliosError::NullPoints => "frame points are null",
+            HeliosError::TooManyPoints => "frame contains too many points",
+            HeliosError::PPSTooHigh => "pps is higher than maximum allowed",
+            HeliosError::PPSTooLow => "pps is lower than minimum allowed",
+            HeliosError::DeviceClosed => "device is closed",
+            HeliosError::DeviceFrameReady => "previous frame has not been sent yet",
+            HeliosError::DeviceSendControl => "sending control message failed",
+            HeliosError::DeviceResult => "device answered with an unexpected result",
+            HeliosError::DeviceNullBuffer => "control message buffer is null",
+            HeliosError::DeviceSignalTooLong => "control message is too long",
+            HeliosError::Libusb(code) => return write!(f, "libusb error {}", code),
+            HeliosError::Unknown(code) => return write!(f, "unknown error {}", code),
+        };
+
+        f.write_str(message)
+    }
 }
 
+impl std::error::Error for HeliosError {}
+
 impl Drop for HeliosDacWrapper {
     fn drop(&mut self) {
         unsafe {
@@ -166,3 +210,17 @@ impl From<Point> for HeliosPoint {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_error_codes() {
+        assert_eq!(parse_error(HELIOS_SUCCESS as i32), Ok(()));
+        assert_eq!(parse_error(HELIOS_ERROR_PPS_TOO_LOW as i32), Err(HeliosError::PPSTooLow));
+        assert_eq!(parse_error(HELIOS_ERROR_DEVICE_CLOSED as i32), Err(HeliosError::DeviceClosed));
+        assert_eq!(parse_error(HELIOS_ERROR_LIBUSB_BASE as i32 - 4), Err(HeliosError::Libusb(-4)));
+        assert_eq!(parse_error(-12345), Err(HeliosError::Unknown(-12345)));
+    }
+}
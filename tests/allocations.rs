@@ -0,0 +1,98 @@
+//! Frame writes after the first one must not allocate, see `NativeHeliosDac::write_frame_ref`
+#![cfg(feature = "native")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::Duration;
+
+use helios_dac::{Frame, MockDac, NativeHeliosDac, NativeHeliosError, Point, Transport};
+use helios_dac::safety::SafetyLimiter;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Answers control messages like the mock but drops frames, as recording them would allocate
+struct DiscardingDac(MockDac);
+
+impl Transport for DiscardingDac {
+    fn write_frame(&self, buffer: &[u8], _: Duration) -> Result<usize, NativeHeliosError> {
+        Ok(buffer.len())
+    }
+
+    fn write_control(&self, buffer: &[u8], timeout: Duration) -> Result<usize, NativeHeliosError> {
+        self.0.write_control(buffer, timeout)
+    }
+
+    fn read_control(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize, NativeHeliosError> {
+        self.0.read_control(buffer, timeout)
+    }
+}
+
+fn frames() -> Vec<Frame> {
+    [10, Frame::MAX_POINTS, 45 + 64, 1]
+        .iter()
+        .map(|count| {
+            let points = (0..*count)
+                .map(|i| Point::builder().position((i % 0x1000) as u16, 0x800).rgb(255, 128, 0).build())
+                .collect();
+            Frame::new(30000, points)
+        })
+        .collect()
+}
+
+#[test]
+fn writing_frames_doesnt_allocate_after_the_first_frame() {
+    let mut dac = NativeHeliosDac::from_transport(DiscardingDac(MockDac::new())).unwrap();
+    let frames = frames();
+    dac.write_frame_ref(&frames[0]).unwrap();
+
+    let before = allocations();
+    for _ in 0..10 {
+        for frame in &frames {
+            dac.write_frame_ref(frame).unwrap();
+        }
+    }
+
+    assert_eq!(allocations() - before, 0);
+}
+
+#[test]
+fn safety_limiting_doesnt_allocate_after_the_first_frame() {
+    let mut dac = NativeHeliosDac::from_transport(DiscardingDac(MockDac::new())).unwrap();
+    dac.set_safety_limiter(Some(SafetyLimiter::new(0.1)));
+    let frames = frames();
+    dac.write_frame_ref(&frames[0]).unwrap();
+
+    let before = allocations();
+    for frame in &frames {
+        dac.write_frame_ref(frame).unwrap();
+    }
+
+    assert_eq!(allocations() - before, 0);
+}